@@ -9,6 +9,10 @@ pub fn print_backend_webserver_code(_project_description_and_template: &str) {
     /// IMPORTANT: The following libraries are already installed
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors
     /// No other external libraries should be used. Write functions that fit with the description from the PROJECT_DESCRIPTION
+    /// IMPORTANT: Like the template, the server binds to 127.0.0.1 on the port read from the PORT environment variable, falling back to 8080
+    /// IMPORTANT: When a DATABASE_SCHEMA and DATA_ACCESS_MODULE are given, the data is stored in SQLite instead of memory.
+    ///   The module is already saved as src/db.rs: declare it with `mod db;`, open the database with db::open("database.sqlite")
+    ///   and call its insert, get, list, update and delete functions. rusqlite is installed for it, do not copy the module
     /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
     println!(OUTPUT)
 }
@@ -22,6 +26,8 @@ pub fn print_improved_webserver_code(_project_description_and_template: &str) {
     ///   3. ONLY writes the code. No commentary.
     /// IMPORTANT: The following libraries are already installed. Does not use ANY libraries other than what was provided in the template
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait
+    /// IMPORTANT: Keeps binding to 127.0.0.1 on the port read from the PORT environment variable, falling back to 8080
+    /// IMPORTANT: When a DATA_ACCESS_MODULE is given, keeps `mod db;` and stores every record through its functions
     println!(OUTPUT)
 }
 
@@ -39,12 +45,12 @@ pub fn print_rest_api_endpoints(_code_input: &str) {
     /// FUNCTION: Prints out the JSON schema for url endpoints and their respective types
     /// LOGIC: Script analyses all code and can categorize into the following object keys:
     ///   "route": This represents the url path of the endpoint
-    ///   "is_route_dynamic": if a route has curly braces in it such as {symbol} or {id} as an example, then this will be set to true
+    ///   "is_route_dynamic": if a route has curly braces in it such as {symbol} or {id} as an example, then this will be set to true, otherwise false
     ///   "method": This represents the method being called
-    ///   "request_body": This represents the body of a post method request
-    ///   "response": This represents the output based upon the structs in the code and understanding the functions
+    ///   "request_body": This represents the body of a post method request, null when there is none
+    ///   "response": This represents the output based upon the structs in the code and understanding the functions, null when there is none
     /// IMPORTANT: Only prints out the JSON schema. No commentary or anything else.
-    /// MUST READ: All keys are strings. Even bool should be wrapped in double quotes as "bool"
+    /// MUST READ: "is_route_dynamic" is a JSON boolean. Field types inside bodies are strings: "number", "string" or "bool"
     /// EXAMPLE:
     /// INPUT_CODE:
     /// ...
@@ -72,9 +78,9 @@ pub fn print_rest_api_endpoints(_code_input: &str) {
     /// [
     ///   {
     ///     "route": "/item/{id}",
-    ///     "is_route_dynamic": "true",
-    ///     "method": "get"
-    ///     "request_body": "None",
+    ///     "is_route_dynamic": true,
+    ///     "method": "get",
+    ///     "request_body": null,
     ///     "response": {
     ///       "id": "number",
     ///       "name": "string",
@@ -83,28 +89,28 @@ pub fn print_rest_api_endpoints(_code_input: &str) {
     ///   },
     ///   {
     ///     "route": "/item",
-    ///     "is_route_dynamic": "false",
+    ///     "is_route_dynamic": false,
     ///     "method": "post",
     ///     "request_body": {
     ///       "id": "number",
     ///       "name": "string",
     ///       "completed": "bool",
     ///     },
-    ///     "response": "None"
+    ///     "response": null
     ///   },
     ///   {
     ///     "route": "/item/{id}",
-    ///     "is_route_dynamic": "true",
+    ///     "is_route_dynamic": true,
     ///     "method": "delete",
-    ///     "request_body": "None",
-    ///     "response": "None"
+    ///     "request_body": null,
+    ///     "response": null
     ///   },
     ///   {
     ///     "route": "/crypto",
-    ///     "is_route_dynamic": "false",
+    ///     "is_route_dynamic": false,
     ///     "method": "get",
-    ///     "request_body": "None",
-    ///     "response": "not_provided"
+    ///     "request_body": null,
+    ///     "response": null
     ///   },
     ///   ... // etc
     /// ]
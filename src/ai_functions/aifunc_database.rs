@@ -0,0 +1,47 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_database_schema(_project_description_and_scope: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and PROJECT_SCOPE for a website that stores data
+    /// FUNCTION: Designs the relational SQLite schema the website backend stores its data in
+    /// LOGIC: One table per kind of record, plural snake_case names. Every table has an "id" column of type "integer" marked "primary_key".
+    ///   A column pointing at a row of another table names that table in "references", e.g. "user_id" references "users"
+    ///   Column types are one of "integer", "real", "text" or "boolean". Columns are required unless "nullable" is true
+    /// IMPORTANT: Only the tables the PROJECT_DESCRIPTION needs. Passwords are stored as a "password_hash" text column
+    /// IMPORTANT: Only prints out the JSON schema. No commentary or anything else.
+    /// EXAMPLE:
+    /// PROJECT_DESCRIPTION: build a website where users log in and keep a todo list
+    /// PRINTS JSON FORMATTED OUTPUT:
+    /// {
+    ///   "tables": [
+    ///     {
+    ///       "name": "users",
+    ///       "columns": [
+    ///         {"name": "id", "type": "integer", "primary_key": true},
+    ///         {"name": "username", "type": "text"},
+    ///         {"name": "password_hash", "type": "text"}
+    ///       ]
+    ///     },
+    ///     {
+    ///       "name": "tasks",
+    ///       "columns": [
+    ///         {"name": "id", "type": "integer", "primary_key": true},
+    ///         {"name": "user_id", "type": "integer", "references": "users"},
+    ///         {"name": "name", "type": "text"},
+    ///         {"name": "completed", "type": "boolean"},
+    ///         {"name": "due_date", "type": "text", "nullable": true}
+    ///       ]
+    ///     }
+    ///   ]
+    /// }
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_database_schema(_broken_schema_with_issues: &str) {
+    /// INPUT: Takes in a JSON database BROKEN_SCHEMA, the PROJECT_DESCRIPTION it was designed for and the ISSUES found in it
+    /// FUNCTION: Fixes every issue while keeping the tables the PROJECT_DESCRIPTION needs
+    /// IMPORTANT: Keeps the same JSON format: {"tables": [{"name": ..., "columns": [{"name": ..., "type": ..., "primary_key": ..., "nullable": ..., "references": ...}]}]}
+    /// IMPORTANT: Only prints out the JSON schema. No commentary or anything else.
+    println!(OUTPUT)
+}
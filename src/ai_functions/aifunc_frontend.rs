@@ -0,0 +1,35 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_frontend_code(_project_description_and_api_endpoints: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION, the API_BASE_URL of a running backend and its API_ENDPOINTS as a JSON schema
+    /// FUNCTION: Writes a static website frontend for the PROJECT_DESCRIPTION that uses the backend through its API_ENDPOINTS
+    /// LOGIC: Each endpoint object has a "route", a "method", the "request_body" it expects and the "response" it returns.
+    ///   Dynamic parts of a route such as {id} are filled in from the page, e.g. the id of the item the user clicked
+    /// IMPORTANT: Writes a single self-contained index.html. CSS goes in a <style> tag and JavaScript in a <script> tag
+    /// IMPORTANT: No build step, frameworks or external libraries. Plain HTML, CSS and JavaScript using fetch() only
+    /// IMPORTANT: Every request starts from a constant API_BASE_URL set to the given API_BASE_URL. Every endpoint is used by the page
+    /// IMPORTANT: Shows errors returned by the backend to the user instead of failing silently
+    /// OUTPUT: Print ONLY the HTML, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_improved_frontend_code(_frontend_code_and_api_endpoints: &str) {
+    /// INPUT: Takes in the FRONTEND_CODE of a static website, its PROJECT_DESCRIPTION and the API_ENDPOINTS of its backend
+    /// FUNCTION: Performs the following tasks:
+    ///   1. Removes any bugs in the HTML and JavaScript and makes the page pleasant to use
+    ///   2. Makes sure every API endpoint is called with the method, route and request body of the schema, and every response is shown
+    ///   3. Makes sure everything requested in the PROJECT_DESCRIPTION is on the page. Everything should be written now.
+    /// IMPORTANT: Keeps a single self-contained index.html without external libraries
+    /// OUTPUT: Print ONLY the HTML, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_frontend_code(_broken_frontend_with_issues: &str) {
+    /// INPUT: Takes in the BROKEN_CODE of a static website, the API_ENDPOINTS of its backend and the ISSUES found in it
+    /// FUNCTION: Fixes every issue, e.g. adds calls to the API endpoints the page does not use yet
+    /// IMPORTANT: Only prints out the new and improved HTML. No commentary or anything else
+    println!(OUTPUT)
+}
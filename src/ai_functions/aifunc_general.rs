@@ -0,0 +1,19 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_repaired_json(_broken_json_and_error: &str) {
+    /// INPUT: Takes in BROKEN_JSON that failed to parse and the DECODE_ERROR returned by the parser
+    /// FUNCTION: Fixes the JSON so it parses. Keeps every key and value that was intended, only the syntax changes
+    /// IMPORTANT: Removes comments, trailing commas, markdown fences and any text around the JSON
+    /// OUTPUT: Prints ONLY the valid JSON, nothing else. No commentary.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_memory_summary(_conversation_transcript: &str) {
+    /// INPUT: Takes in a TRANSCRIPT of earlier prompts and answers of an agent building a website
+    /// FUNCTION: Condenses the transcript into a short summary the agent can continue working from
+    /// IMPORTANT: Keeps decisions taken, requirements, errors found and how they were fixed. Drops repeated code
+    /// OUTPUT: Prints ONLY the summary as plain text, nothing else. No commentary.
+    println!(OUTPUT)
+}
@@ -1,75 +1,112 @@
-use crate::models::general::llm::{APIResponse, ChatCompletion, Message};
-use dotenv::dotenv;
-use std::env;
-
-use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Client,
-};
-
-// Calls Large Language Model (i.e. GTP-4)
-pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn std::error::Error + Send>> {
-    dotenv().ok();
-
-    // Extracts API Key infromations
-    let api_key: String =
-        env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY not found in env variables");
-    let api_org: String = env::var("OPENAI_ORG").expect("OPENAI_ORG not found in env variables");
-
-    // Confirms endpoint
-    let url: &str = "https://api.openai.com/v1/chat/completions";
-
-    // Creates headers
-    let mut headers: HeaderMap = HeaderMap::new();
-
-    // Creates API key header
-    headers.insert(
-        "authorization",
-        HeaderValue::from_str(&format!("Bearer {}", api_key))
-            .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?,
-    );
-
-    // Creates OpenAI Org header
-    headers.insert(
-        "OpenAI-Organization",
-        HeaderValue::from_str(api_org.as_str())
-            .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?,
-    );
-
-    // Creates client
-    let client = Client::builder()
-        .default_headers(headers)
-        .build()
-        .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?;
-
-    // Create chat completion
-    let chat_completion: ChatCompletion = ChatCompletion {
-        model: "gpt-3.5-turbo".to_string(),
-        messages,
-        temperature: 0.1,
-    };
-
-    // Extracts API response
-    let res: APIResponse = client
-        .post(url)
-        .json(&chat_completion)
-        .send()
-        .await
-        .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?
-        .json()
-        .await
-        .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?;
-
-    // send response
-    Ok(res.choices[0].message.content.clone())
+use crate::apis::cassette::active_cassette;
+use crate::apis::providers::{active_provider, ChatStream, LlmProvider};
+use crate::apis::retry::RetryPolicy;
+use crate::errors::GippityError;
+use crate::helpers::command_line::{PrintCommand, Verbosity};
+use crate::models::general::llm::{ChatCompletion, ChatDelta, LlmResponse, Message, Usage};
+
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+// Calls Large Language Model (i.e. GTP-4) through the configured provider,
+// retrying transient failures according to the retry policy
+pub async fn call_gpt(messages: Vec<Message>) -> Result<LlmResponse, GippityError> {
+    let provider: Arc<dyn LlmProvider> = active_provider()?;
+    let retry_policy: RetryPolicy = RetryPolicy::from_env()?;
+
+    let mut attempt: u32 = 0;
+    let call_with_retries = retry_policy.run(|| {
+        attempt += 1;
+        let is_retry: bool = attempt > 1;
+        let provider: &dyn LlmProvider = provider.as_ref();
+        let messages: &Vec<Message> = &messages;
+
+        async move {
+            if provider.streaming() {
+                // A retried stream starts over, so whatever the failed attempt printed is stale
+                if is_retry {
+                    PrintCommand::Issue.print_agent_message(
+                        "LLM Client",
+                        "Restarting the stream, discard the partial answer above",
+                    );
+                }
+                stream_chat(provider, messages.clone()).await
+            } else {
+                let res: LlmResponse = provider.chat(messages.clone()).await?;
+                if Verbosity::current() == Verbosity::Verbose {
+                    PrintCommand::AICall.print_agent_message("LLM Client", &res.content);
+                }
+                Ok(res)
+            }
+        }
+    });
+
+    // Records or replays the exchange when a cassette is in use
+    match active_cassette()? {
+        Some(cassette) => {
+            let chat_completion: ChatCompletion = ChatCompletion {
+                model: provider.model().to_string(),
+                messages: messages.clone(),
+                temperature: provider.temperature(),
+            };
+            cassette.chat(&chat_completion, call_with_retries).await
+        }
+        None => call_with_retries.await,
+    }
+}
+
+// Renders the answer while it streams in and returns it assembled
+async fn stream_chat(
+    provider: &dyn LlmProvider,
+    messages: Vec<Message>,
+) -> Result<LlmResponse, GippityError> {
+    let mut chat_stream: ChatStream = provider.chat_stream(messages).await?;
+
+    let mut content: String = String::new();
+    let mut usage: Option<Usage> = None;
+    while let Some(delta) = chat_stream.next().await {
+        let delta: ChatDelta = match delta {
+            Ok(delta) => delta,
+            Err(e) => {
+                // Keeps the next message off the partly rendered line
+                if !content.is_empty() {
+                    println!();
+                }
+                return Err(e);
+            }
+        };
+
+        PrintCommand::AICall.print_stream_delta(&delta.content);
+        content.push_str(&delta.content);
+        usage = delta.usage.or(usage);
+    }
+
+    if content.is_empty() {
+        return Err(GippityError::EmptyResponse);
+    }
+    println!();
+
+    Ok(LlmResponse {
+        content,
+        model: provider.model().to_string(),
+        usage,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apis::mock_server::MockLlmServer;
+    use crate::apis::providers::scripted::{ScriptedProvider, ANY_FUNCTION};
+    use crate::apis::providers::with_provider;
 
     #[tokio::test]
     async fn tests_call_to_openai() {
+        let server: MockLlmServer = MockLlmServer::start(
+            ScriptedProvider::new().with_response(ANY_FUNCTION, "Hi! This is a short response."),
+        )
+        .await;
+
         let message = Message {
             role: "user".to_string(),
             content: "Hi there, this is a test. Give me a short response".to_string(),
@@ -77,15 +114,46 @@ mod tests {
 
         let messages = vec![message];
 
-        let res: Result<String, Box<dyn std::error::Error + Send>> = call_gpt(messages).await;
+        let res: Result<LlmResponse, GippityError> =
+            with_provider(server.provider(), call_gpt(messages)).await;
         match res {
-            Ok(res_str) => {
-                dbg!(res_str);
-                assert!(true);
+            Ok(res) => {
+                dbg!(&res);
+                assert_eq!(res.content, "Hi! This is a short response.");
+                assert_eq!(res.model, "gpt-mock");
+                assert!(res.usage.unwrap().completion_tokens > 0);
             }
             Err(_) => {
-                assert!(false);
+                panic!("Failed to call the mock LLM server");
             }
         }
+
+        assert_eq!(server.received().len(), 1);
+        assert_eq!(server.received()[0].model, "gpt-mock");
+    }
+
+    #[tokio::test]
+    async fn tests_streams_call_to_openai() {
+        let server: MockLlmServer = MockLlmServer::start(ScriptedProvider::new().with_response(
+            ANY_FUNCTION,
+            "Hi! This answer arrives a few tokens at a time.",
+        ))
+        .await;
+
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: "Hi there, stream me a short response".to_string(),
+        }];
+
+        let res: LlmResponse = with_provider(server.streaming_provider(), call_gpt(messages))
+            .await
+            .expect("Failed to stream from the mock LLM server");
+
+        assert_eq!(
+            res.content,
+            "Hi! This answer arrives a few tokens at a time."
+        );
+        assert_eq!(res.model, "gpt-mock");
+        assert!(res.usage.unwrap().completion_tokens > 0);
     }
 }
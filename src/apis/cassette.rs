@@ -0,0 +1,310 @@
+use crate::errors::GippityError;
+use crate::models::general::llm::{ChatCompletion, LlmResponse};
+use crate::runner::contract::{RouteRequest, RouteResult};
+
+use dotenv::dotenv;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use strum_macros::EnumString;
+
+// User request a cassette was recorded for
+const PROMPT_FILE: &str = "prompt.txt";
+
+// Cassette shared by every call made by this process
+static ACTIVE_CASSETTE: OnceLock<Option<Arc<Cassette>>> = OnceLock::new();
+
+#[cfg(test)]
+tokio::task_local! {
+    static CASSETTE_OVERRIDE: Arc<Cassette>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum CassetteMode {
+    Off,
+    Record,
+    Replay,
+}
+
+// One recorded request/response pair
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    pub kind: String,
+    pub key: String,
+    pub request: serde_json::Value,
+    pub response: serde_json::Value,
+}
+
+// Records real traffic to a directory and replays it later without touching the network
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    dir: PathBuf,
+    // Times each key was seen, so identical requests replay in the same order
+    counters: Mutex<HashMap<String, usize>>,
+}
+
+impl Cassette {
+    pub fn new(mode: CassetteMode, dir: PathBuf) -> Self {
+        Self {
+            mode,
+            dir,
+            counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Reads LLM_CASSETTE_MODE (off | record | replay) and LLM_CASSETTE_DIR (defaults to "cassettes")
+    pub fn from_env() -> Result<Option<Self>, GippityError> {
+        dotenv().ok();
+
+        let mode: CassetteMode = match env::var("LLM_CASSETTE_MODE") {
+            Ok(mode_str) => CassetteMode::from_str(mode_str.trim())?,
+            Err(_) => CassetteMode::Off,
+        };
+
+        if mode == CassetteMode::Off {
+            return Ok(None);
+        }
+
+        let dir: String = env::var("LLM_CASSETTE_DIR").unwrap_or_else(|_| "cassettes".to_string());
+        Ok(Some(Self::new(mode, PathBuf::from(dir))))
+    }
+
+    // Makes this the cassette used by the rest of the process. Must happen before first use
+    pub fn init(self) -> Result<Arc<Cassette>, GippityError> {
+        let cassette: Arc<Cassette> = Arc::new(self);
+        ACTIVE_CASSETTE
+            .set(Some(cassette.clone()))
+            .map_err(|_| GippityError::Config("Cassette is already initialised".to_string()))?;
+        Ok(cassette)
+    }
+
+    // Keeps the user request next to a recording, so the run can be replayed later
+    pub fn record_prompt(&self, prompt: &str) -> Result<(), GippityError> {
+        if self.mode == CassetteMode::Record {
+            fs::create_dir_all(&self.dir)?;
+            fs::write(self.dir.join(PROMPT_FILE), prompt)?;
+        }
+        Ok(())
+    }
+
+    // User request the recording was made for
+    pub fn recorded_prompt(&self) -> Result<String, GippityError> {
+        let path: PathBuf = self.dir.join(PROMPT_FILE);
+        fs::read_to_string(&path).map_err(|e| {
+            GippityError::Config(format!("No recorded prompt at {}: {}", path.display(), e))
+        })
+    }
+
+    // Records or replays a chat completion, keyed by its request body
+    pub async fn chat<F>(
+        &self,
+        chat_completion: &ChatCompletion,
+        call: F,
+    ) -> Result<LlmResponse, GippityError>
+    where
+        F: Future<Output = Result<LlmResponse, GippityError>>,
+    {
+        self.exchange("llm", chat_completion, call).await
+    }
+
+    // Records or replays a URL status check, keyed by the URL
+    pub async fn status_code<F>(&self, url: &str, call: F) -> Result<u16, GippityError>
+    where
+        F: Future<Output = Result<u16, GippityError>>,
+    {
+        self.exchange("url", &url, call).await
+    }
+
+    // Records or replays a contract check of the generated server, keyed by the request
+    pub async fn route_check<F>(
+        &self,
+        route_request: &RouteRequest,
+        call: F,
+    ) -> Result<RouteResult, GippityError>
+    where
+        F: Future<Output = Result<RouteResult, GippityError>>,
+    {
+        self.exchange("route", route_request, call).await
+    }
+
+    async fn exchange<Req, Res, F>(
+        &self,
+        kind: &str,
+        request: &Req,
+        call: F,
+    ) -> Result<Res, GippityError>
+    where
+        Req: Serialize,
+        Res: Serialize + DeserializeOwned,
+        F: Future<Output = Result<Res, GippityError>>,
+    {
+        let request_json: serde_json::Value = serde_json::to_value(request)?;
+        let key: String = format!("{}-{:016x}", kind, fnv1a_hash(&request_json.to_string()));
+        let path: PathBuf = self.next_path(&key);
+
+        match self.mode {
+            CassetteMode::Off => call.await,
+
+            CassetteMode::Replay => {
+                let entry_str: String = fs::read_to_string(&path).map_err(|e| {
+                    GippityError::Config(format!(
+                        "No recording for {} request in cassette ({}): {}",
+                        kind,
+                        path.display(),
+                        e
+                    ))
+                })?;
+                let entry: CassetteEntry = serde_json::from_str(&entry_str)?;
+
+                Ok(serde_json::from_value(entry.response)?)
+            }
+
+            CassetteMode::Record => {
+                let response: Res = call.await?;
+
+                let entry: CassetteEntry = CassetteEntry {
+                    kind: kind.to_string(),
+                    key,
+                    request: request_json,
+                    response: serde_json::to_value(&response)?,
+                };
+                let entry_str: String = serde_json::to_string_pretty(&entry)?;
+
+                fs::create_dir_all(&self.dir)?;
+                fs::write(&path, entry_str)?;
+
+                Ok(response)
+            }
+        }
+    }
+
+    // Path of the next recording for the key, e.g. cassettes/llm-<hash>-0.json
+    fn next_path(&self, key: &str) -> PathBuf {
+        let mut counters = self.counters.lock().unwrap();
+        let counter: &mut usize = counters.entry(key.to_string()).or_insert(0);
+        let path: PathBuf = self.dir.join(format!("{}-{}.json", key, counter));
+        *counter += 1;
+        path
+    }
+}
+
+// Gets the cassette for the current call, built once from the env configuration
+pub fn active_cassette() -> Result<Option<Arc<Cassette>>, GippityError> {
+    #[cfg(test)]
+    if let Ok(cassette) = CASSETTE_OVERRIDE.try_with(|cassette| cassette.clone()) {
+        return Ok(Some(cassette));
+    }
+
+    if let Some(cassette) = ACTIVE_CASSETTE.get() {
+        return Ok(cassette.clone());
+    }
+
+    let cassette: Option<Arc<Cassette>> = Cassette::from_env()?.map(Arc::new);
+    Ok(ACTIVE_CASSETTE.get_or_init(|| cassette).clone())
+}
+
+// Runs the future with every call going through the given cassette
+#[cfg(test)]
+pub async fn with_cassette<F: Future>(cassette: Arc<Cassette>, fut: F) -> F::Output {
+    CASSETTE_OVERRIDE.scope(cassette, fut).await
+}
+
+// 64-bit FNV-1a, stable across runs and Rust versions unlike DefaultHasher
+fn fnv1a_hash(input: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in input.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::call_request::call_gpt;
+    use crate::apis::providers::scripted::{ScriptedProvider, ANY_FUNCTION};
+    use crate::apis::providers::with_provider;
+    use crate::helpers::workspace::TempWorkspace;
+    use crate::models::general::llm::Message;
+
+    #[test]
+    fn tests_fnv1a_hash_is_stable() {
+        assert_eq!(fnv1a_hash(""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[tokio::test]
+    async fn tests_records_and_replays_llm_calls() {
+        let temp_dir: TempWorkspace = TempWorkspace::new("cassette_llm");
+        let dir: PathBuf = temp_dir.root.clone();
+        let messages: Vec<Message> = vec![Message {
+            role: "user".to_string(),
+            content: "Hi there, this is a test".to_string(),
+        }];
+
+        // Records real answers
+        let recorder = Arc::new(Cassette::new(CassetteMode::Record, dir.clone()));
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response(ANY_FUNCTION, "first answer")
+                .with_response(ANY_FUNCTION, "second answer"),
+        );
+        let recorded: Vec<String> = with_provider(
+            provider,
+            with_cassette(recorder, async {
+                vec![
+                    call_gpt(messages.clone()).await.unwrap().content,
+                    call_gpt(messages.clone()).await.unwrap().content,
+                ]
+            }),
+        )
+        .await;
+
+        // Replays them with a provider that has nothing to say
+        let player = Arc::new(Cassette::new(CassetteMode::Replay, dir.clone()));
+        let replayed: Vec<String> = with_provider(
+            Arc::new(ScriptedProvider::new()),
+            with_cassette(player, async {
+                vec![
+                    call_gpt(messages.clone()).await.unwrap().content,
+                    call_gpt(messages.clone()).await.unwrap().content,
+                ]
+            }),
+        )
+        .await;
+
+        assert_eq!(recorded, vec!["first answer", "second answer"]);
+        assert_eq!(recorded, replayed);
+    }
+
+    #[tokio::test]
+    async fn tests_replays_url_status_codes() {
+        let temp_dir: TempWorkspace = TempWorkspace::new("cassette_url");
+        let dir: PathBuf = temp_dir.root.clone();
+        let url: &str = "https://api.binance.com/api/v3/exchangeInfo";
+
+        let recorder: Cassette = Cassette::new(CassetteMode::Record, dir.clone());
+        let recorded: u16 = recorder.status_code(url, async { Ok(404) }).await.unwrap();
+
+        let player: Cassette = Cassette::new(CassetteMode::Replay, dir.clone());
+        let replayed: u16 = player
+            .status_code(url, async { panic!("Replay must not touch the network") })
+            .await
+            .unwrap();
+        let missing = player
+            .status_code("https://unknown.url", async { Ok(200) })
+            .await;
+
+        assert_eq!(recorded, replayed);
+        assert!(missing.is_err());
+    }
+}
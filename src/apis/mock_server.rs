@@ -0,0 +1,212 @@
+use crate::apis::providers::openai::OpenAIProvider;
+use crate::apis::providers::scripted::ScriptedProvider;
+use crate::apis::providers::{LlmConfig, LlmProvider, ProviderKind};
+use crate::models::general::llm::{ChatCompletion, Usage};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+// Local stand-in for the OpenAI chat completions endpoint. Completions are
+// answered from a script keyed by ai_function name, any other request gets a 200
+pub struct MockLlmServer {
+    addr: SocketAddr,
+    received: Arc<Mutex<Vec<ChatCompletion>>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockLlmServer {
+    pub async fn start(script: ScriptedProvider) -> Self {
+        let script: Arc<ScriptedProvider> = Arc::new(script);
+        let received: Arc<Mutex<Vec<ChatCompletion>>> = Arc::new(Mutex::new(vec![]));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let service_script: Arc<ScriptedProvider> = script.clone();
+        let service_received: Arc<Mutex<Vec<ChatCompletion>>> = received.clone();
+        let make_service = make_service_fn(move |_| {
+            let script: Arc<ScriptedProvider> = service_script.clone();
+            let received: Arc<Mutex<Vec<ChatCompletion>>> = service_received.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    handle_request(req, script.clone(), received.clone())
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_rx.await.ok();
+        }));
+
+        Self {
+            addr,
+            received,
+            shutdown: Some(shutdown_tx),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    // Chat completions received so far
+    pub fn received(&self) -> Vec<ChatCompletion> {
+        self.received.lock().unwrap().clone()
+    }
+
+    // OpenAI provider talking to this server
+    pub fn provider(&self) -> Arc<dyn LlmProvider> {
+        Arc::new(OpenAIProvider::new(self.config()))
+    }
+
+    // OpenAI provider talking to this server over server-sent events
+    pub fn streaming_provider(&self) -> Arc<dyn LlmProvider> {
+        Arc::new(OpenAIProvider::new(LlmConfig {
+            stream: true,
+            ..self.config()
+        }))
+    }
+
+    fn config(&self) -> LlmConfig {
+        LlmConfig {
+            provider: ProviderKind::OpenAI,
+            model: "gpt-mock".to_string(),
+            base_url: self.base_url(),
+            api_key: Some("mock-key".to_string()),
+            organization: None,
+            temperature: 0.1,
+            max_tokens: 4096,
+            script_path: None,
+            stream: false,
+        }
+    }
+}
+
+impl Drop for MockLlmServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    script: Arc<ScriptedProvider>,
+    received: Arc<Mutex<Vec<ChatCompletion>>>,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST || !req.uri().path().ends_with("/chat/completions") {
+        return Ok(Response::new(Body::from("OK")));
+    }
+
+    let body_bytes = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+    let chat_completion: ChatCompletion = match serde_json::from_slice(&body_bytes) {
+        Ok(chat_completion) => chat_completion,
+        Err(e) => return Ok(error_response(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+    let stream: bool = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+        .map(|body_json| body_json["stream"] == json!(true))
+        .unwrap_or(false);
+
+    let content: Option<String> = script.next_response(&chat_completion.messages);
+    let usage: Usage = Usage::estimate(
+        &chat_completion.messages,
+        content.as_deref().unwrap_or_default(),
+    );
+    let model: String = chat_completion.model.clone();
+    received.lock().unwrap().push(chat_completion);
+
+    let Some(content) = content else {
+        return Ok(error_response(
+            StatusCode::NOT_FOUND,
+            "No scripted response for this ai_function",
+        ));
+    };
+
+    if stream {
+        return Ok(event_stream_response(&model, &content, usage));
+    }
+
+    let response_json = json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": usage.prompt_tokens,
+            "completion_tokens": usage.completion_tokens,
+            "total_tokens": usage.prompt_tokens + usage.completion_tokens
+        }
+    });
+
+    Ok(json_response(StatusCode::OK, response_json.to_string()))
+}
+
+// Sends the content a few characters per event, then the usage and [DONE]
+fn event_stream_response(model: &str, content: &str, usage: Usage) -> Response<Body> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut body: String = String::new();
+
+    for piece in chars.chunks(4) {
+        let chunk_json = json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion.chunk",
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "delta": { "content": piece.iter().collect::<String>() },
+                "finish_reason": null
+            }]
+        });
+        body.push_str(&format!("data: {}\n\n", chunk_json));
+    }
+
+    let usage_json = json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion.chunk",
+        "model": model,
+        "choices": [],
+        "usage": {
+            "prompt_tokens": usage.prompt_tokens,
+            "completion_tokens": usage.completion_tokens,
+            "total_tokens": usage.prompt_tokens + usage.completion_tokens
+        }
+    });
+    body.push_str(&format!("data: {}\n\ndata: [DONE]\n\n", usage_json));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let error_json = json!({
+        "error": { "message": message, "type": "invalid_request_error" }
+    });
+    json_response(status, error_json.to_string())
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
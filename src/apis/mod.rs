@@ -1 +1,7 @@
 pub mod call_request;
+pub mod cassette;
+#[cfg(test)]
+pub mod mock_server;
+pub mod providers;
+pub mod retry;
+pub mod sse;
@@ -0,0 +1,174 @@
+use crate::apis::providers::{read_json_response, LlmConfig, LlmProvider};
+use crate::errors::GippityError;
+use crate::models::general::llm::{LlmResponse, Message, Usage};
+
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Debug, Serialize)]
+struct AnthropicRequest {
+    model: String,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContent {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+// Anthropic Messages API
+#[derive(Debug)]
+pub struct AnthropicProvider {
+    config: LlmConfig,
+}
+
+impl AnthropicProvider {
+    pub fn new(config: LlmConfig) -> Self {
+        Self { config }
+    }
+
+    // The Messages API takes system prompts apart and needs at least one user turn
+    fn build_request(&self, messages: Vec<Message>) -> AnthropicRequest {
+        let (system_msgs, mut chat_msgs): (Vec<Message>, Vec<Message>) = messages
+            .into_iter()
+            .partition(|message| message.role == "system");
+
+        let system_str: String = system_msgs
+            .into_iter()
+            .map(|message| message.content)
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let system: Option<String> = if system_str.is_empty() {
+            None
+        } else if chat_msgs.is_empty() {
+            chat_msgs.push(Message {
+                role: "user".to_string(),
+                content: system_str,
+            });
+            None
+        } else {
+            Some(system_str)
+        };
+
+        AnthropicRequest {
+            model: self.config.model.clone(),
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+            system,
+            messages: chat_msgs,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.config.temperature
+    }
+
+    async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse, GippityError> {
+        let api_key: &String = self.config.api_key.as_ref().ok_or_else(|| {
+            GippityError::Config("ANTHROPIC_API_KEY not found in env variables".to_string())
+        })?;
+
+        // Creates headers
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_str(api_key)?);
+        headers.insert(
+            "anthropic-version",
+            HeaderValue::from_static(ANTHROPIC_VERSION),
+        );
+
+        let client: Client = Client::builder().default_headers(headers).build()?;
+
+        let url: String = format!("{}/messages", self.config.base_url);
+        let request: AnthropicRequest = self.build_request(messages);
+
+        let res: AnthropicResponse =
+            read_json_response(client.post(url).json(&request).send().await?).await?;
+
+        let usage: Option<Usage> = res.usage.map(|usage| Usage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        });
+        let content: String = res
+            .content
+            .into_iter()
+            .map(|content| content.text)
+            .collect::<Vec<String>>()
+            .join("");
+
+        if content.is_empty() {
+            return Err(GippityError::EmptyResponse);
+        }
+        Ok(LlmResponse {
+            content,
+            model: self.config.model.clone(),
+            usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::providers::ProviderKind;
+
+    fn test_config() -> LlmConfig {
+        LlmConfig {
+            provider: ProviderKind::Anthropic,
+            model: "claude-test".to_string(),
+            base_url: "http://localhost".to_string(),
+            api_key: None,
+            organization: None,
+            temperature: 0.1,
+            max_tokens: 1024,
+            script_path: None,
+            stream: false,
+        }
+    }
+
+    #[test]
+    fn tests_system_only_prompt_becomes_user_turn() {
+        let provider: AnthropicProvider = AnthropicProvider::new(test_config());
+        let request: AnthropicRequest = provider.build_request(vec![Message {
+            role: "system".to_string(),
+            content: "FUNCTION print_project_scope".to_string(),
+        }]);
+
+        assert!(request.system.is_none());
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].role, "user");
+    }
+}
@@ -0,0 +1,415 @@
+use crate::errors::GippityError;
+pub mod anthropic;
+pub mod ollama;
+pub mod openai;
+pub mod scripted;
+
+use crate::models::general::llm::{ChatDelta, LlmResponse, Message};
+use anthropic::AnthropicProvider;
+use async_trait::async_trait;
+use ollama::OllamaProvider;
+use openai::OpenAIProvider;
+use scripted::ScriptedProvider;
+
+use dotenv::dotenv;
+use futures_util::stream::{self, Stream};
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use std::env;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use strum_macros::{Display, EnumString};
+
+// Backends able to answer chat requests
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[strum(ascii_case_insensitive)]
+pub enum ProviderKind {
+    #[strum(serialize = "openai")]
+    OpenAI,
+    #[strum(serialize = "anthropic")]
+    Anthropic,
+    #[strum(serialize = "ollama")]
+    Ollama,
+    #[strum(serialize = "llamacpp", serialize = "llama.cpp")]
+    LlamaCpp,
+    #[strum(serialize = "scripted", serialize = "mock")]
+    Scripted,
+}
+
+// Deltas of an answer in the order the model generates them
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<ChatDelta, GippityError>> + Send>>;
+
+// Provider shared by every call made by this process
+static ACTIVE_PROVIDER: OnceLock<Arc<dyn LlmProvider>> = OnceLock::new();
+
+#[cfg(test)]
+tokio::task_local! {
+    static PROVIDER_OVERRIDE: Arc<dyn LlmProvider>;
+}
+
+impl ProviderKind {
+    fn default_model(&self) -> &'static str {
+        match self {
+            Self::OpenAI => "gpt-3.5-turbo",
+            Self::Anthropic => "claude-3-5-sonnet-latest",
+            Self::Ollama => "llama3",
+            Self::LlamaCpp => "default",
+            Self::Scripted => "scripted",
+        }
+    }
+
+    fn default_base_url(&self) -> &'static str {
+        match self {
+            Self::OpenAI => "https://api.openai.com/v1",
+            Self::Anthropic => "https://api.anthropic.com/v1",
+            Self::Ollama => "http://localhost:11434",
+            Self::LlamaCpp => "http://localhost:8080/v1",
+            Self::Scripted => "",
+        }
+    }
+
+    // Env variable holding the API key when LLM_API_KEY is not set
+    fn api_key_env(&self) -> Option<&'static str> {
+        match self {
+            Self::OpenAI => Some("OPENAI_API_KEY"),
+            Self::Anthropic => Some("ANTHROPIC_API_KEY"),
+            Self::Ollama | Self::LlamaCpp | Self::Scripted => None,
+        }
+    }
+}
+
+// Settings used to build a provider
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    pub provider: ProviderKind,
+    pub model: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub organization: Option<String>,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    pub script_path: Option<String>,
+    pub stream: bool,
+}
+
+impl LlmConfig {
+    // Reads the configuration from env variables (and the .env file)
+    //   LLM_PROVIDER: openai | anthropic | ollama | llamacpp | scripted (defaults to openai)
+    //   LLM_MODEL, LLM_BASE_URL, LLM_API_KEY, LLM_TEMPERATURE, LLM_MAX_TOKENS
+    //   LLM_SCRIPT_PATH: JSON file of canned outputs used by the scripted provider
+    //   LLM_STREAM: true to render answers token by token where the provider supports it
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let provider: ProviderKind = match env::var("LLM_PROVIDER") {
+            Ok(provider_str) => ProviderKind::from_str(provider_str.trim())?,
+            Err(_) => ProviderKind::OpenAI,
+        };
+
+        let model: String =
+            env::var("LLM_MODEL").unwrap_or_else(|_| provider.default_model().to_string());
+        let base_url: String = env::var("LLM_BASE_URL")
+            .unwrap_or_else(|_| provider.default_base_url().to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        let api_key: Option<String> = env::var("LLM_API_KEY")
+            .ok()
+            .or_else(|| provider.api_key_env().and_then(|key| env::var(key).ok()));
+        let organization: Option<String> = env::var("OPENAI_ORG").ok();
+
+        let temperature: f32 = match env::var("LLM_TEMPERATURE") {
+            Ok(temperature_str) => temperature_str.parse()?,
+            Err(_) => 0.1,
+        };
+        let max_tokens: u32 = match env::var("LLM_MAX_TOKENS") {
+            Ok(max_tokens_str) => max_tokens_str.parse()?,
+            Err(_) => 4096,
+        };
+        let script_path: Option<String> = env::var("LLM_SCRIPT_PATH").ok();
+        let stream: bool = match env::var("LLM_STREAM") {
+            Ok(stream_str) => stream_str.trim().parse()?,
+            Err(_) => false,
+        };
+
+        Ok(Self {
+            provider,
+            model,
+            base_url,
+            api_key,
+            organization,
+            temperature,
+            max_tokens,
+            script_path,
+            stream,
+        })
+    }
+}
+
+// Anything able to turn a list of chat messages into the model's answer
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    // Model answering the requests
+    fn model(&self) -> &str;
+    fn temperature(&self) -> f32;
+    async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse, GippityError>;
+
+    // Whether chat_stream delivers the answer while it is being generated
+    fn streaming(&self) -> bool {
+        false
+    }
+
+    // Answer as a stream of deltas. Providers without streaming send it in one piece
+    async fn chat_stream(&self, messages: Vec<Message>) -> Result<ChatStream, GippityError> {
+        let res: LlmResponse = self.chat(messages).await?;
+        let delta: ChatDelta = ChatDelta {
+            content: res.content,
+            usage: res.usage,
+        };
+        Ok(Box::pin(stream::iter([Ok(delta)])))
+    }
+}
+
+// Builds the provider selected by the configuration
+pub fn build_provider(config: LlmConfig) -> Result<Arc<dyn LlmProvider>, GippityError> {
+    let provider: Arc<dyn LlmProvider> = match config.provider {
+        ProviderKind::OpenAI | ProviderKind::LlamaCpp => Arc::new(OpenAIProvider::new(config)),
+        ProviderKind::Anthropic => Arc::new(AnthropicProvider::new(config)),
+        ProviderKind::Ollama => Arc::new(OllamaProvider::new(config)),
+        ProviderKind::Scripted => {
+            let script_path: String = config.script_path.ok_or_else(|| {
+                GippityError::Config("LLM_SCRIPT_PATH not found in env variables".to_string())
+            })?;
+            Arc::new(ScriptedProvider::from_file(&script_path)?)
+        }
+    };
+
+    Ok(provider)
+}
+
+// Reads a JSON response body, turning non-success statuses into typed errors
+pub async fn read_json_response<T: DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, GippityError> {
+    let body: String = check_response(response).await?.text().await?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+// Passes successful responses through, turning non-success statuses into typed errors
+pub async fn check_response(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, GippityError> {
+    let status: u16 = response.status().as_u16();
+    if (200..300).contains(&status) {
+        return Ok(response);
+    }
+
+    let retry_after: Option<Duration> = retry_after_from_headers(response.headers());
+    let body: String = response.text().await?;
+    Err(status_error(status, body, retry_after))
+}
+
+// OpenAI and Anthropic nest the message in {"error": {"message": ...}}, Ollama uses {"error": "..."}
+fn status_error(status: u16, body: String, retry_after: Option<Duration>) -> GippityError {
+    let message: Option<String> = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body_json| {
+            body_json["error"]["message"]
+                .as_str()
+                .or(body_json["error"].as_str())
+                .map(|message| message.to_string())
+        });
+
+    match message {
+        Some(message) => GippityError::Api {
+            status,
+            message,
+            retry_after,
+        },
+        None => GippityError::HttpStatus {
+            status,
+            body,
+            retry_after,
+        },
+    }
+}
+
+// Reads Retry-After (in seconds) or, failing that, OpenAI's rate limit reset headers
+fn retry_after_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    let header_str = |name: &str| -> Option<&str> {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim())
+    };
+
+    // Values no Duration can hold, e.g. "inf" or "1e400", count as missing
+    let retry_after: Option<Duration> = header_str("retry-after")
+        .and_then(|value| value.parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds.max(0.0)).ok());
+    if retry_after.is_some() {
+        return retry_after;
+    }
+
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| header_str(name).and_then(parse_reset_duration))
+        .max()
+}
+
+// Parses OpenAI reset durations such as "20ms", "1s", "6m0s" or "1h2m3.5s"
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total_secs: f64 = 0.0;
+    let mut number: String = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        total_secs += match (c, chars.peek()) {
+            ('m', Some('s')) => {
+                chars.next();
+                amount / 1000.0
+            }
+            ('h', _) => amount * 3600.0,
+            ('m', _) => amount * 60.0,
+            ('s', _) => amount,
+            _ => return None,
+        };
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+    Duration::try_from_secs_f64(total_secs).ok()
+}
+
+// Makes the provider built from this configuration the one used by the process. Must happen before first use
+pub fn init_provider(config: LlmConfig) -> Result<(), GippityError> {
+    ACTIVE_PROVIDER
+        .set(build_provider(config)?)
+        .map_err(|_| GippityError::Config("LLM provider is already initialised".to_string()))
+}
+
+// Gets the provider for the current call, built once from the env configuration
+pub fn active_provider() -> Result<Arc<dyn LlmProvider>, GippityError> {
+    #[cfg(test)]
+    if let Ok(provider) = PROVIDER_OVERRIDE.try_with(|provider| provider.clone()) {
+        return Ok(provider);
+    }
+
+    if let Some(provider) = ACTIVE_PROVIDER.get() {
+        return Ok(provider.clone());
+    }
+
+    let provider: Arc<dyn LlmProvider> = build_provider(LlmConfig::from_env()?)?;
+    Ok(ACTIVE_PROVIDER.get_or_init(|| provider).clone())
+}
+
+// Runs the future with every LLM call answered by the given provider
+#[cfg(test)]
+pub async fn with_provider<F: std::future::Future>(
+    provider: Arc<dyn LlmProvider>,
+    fut: F,
+) -> F::Output {
+    PROVIDER_OVERRIDE.scope(provider, fut).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parses_provider_kind() {
+        assert_eq!(
+            ProviderKind::from_str("openai").unwrap(),
+            ProviderKind::OpenAI
+        );
+        assert_eq!(
+            ProviderKind::from_str("Anthropic").unwrap(),
+            ProviderKind::Anthropic
+        );
+        assert_eq!(
+            ProviderKind::from_str("llama.cpp").unwrap(),
+            ProviderKind::LlamaCpp
+        );
+        assert_eq!(
+            ProviderKind::from_str("mock").unwrap(),
+            ProviderKind::Scripted
+        );
+        assert!(ProviderKind::from_str("unknown").is_err());
+    }
+
+    #[test]
+    fn tests_reads_api_error_bodies() {
+        let openai_body: String =
+            r#"{"error": {"message": "Invalid API key", "type": "invalid_request_error"}}"#
+                .to_string();
+        let ollama_body: String = r#"{"error": "model not found"}"#.to_string();
+
+        match status_error(401, openai_body, None) {
+            GippityError::Api {
+                status, message, ..
+            } => {
+                assert_eq!(status, 401);
+                assert_eq!(message, "Invalid API key");
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+        assert!(matches!(
+            status_error(404, ollama_body, None),
+            GippityError::Api { .. }
+        ));
+        assert!(matches!(
+            status_error(502, "Bad Gateway".to_string(), None),
+            GippityError::HttpStatus { status: 502, .. }
+        ));
+    }
+
+    #[test]
+    fn tests_reads_retry_after_headers() {
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert("x-ratelimit-reset-requests", "6m0s".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "20ms".parse().unwrap());
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_secs(360))
+        );
+
+        headers.insert("retry-after", "2".parse().unwrap());
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_secs(2))
+        );
+
+        assert_eq!(
+            parse_reset_duration("1m30.5s"),
+            Some(Duration::from_secs_f64(90.5))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+
+        // Out of range values from the server are ignored rather than panicking
+        headers.insert("retry-after", "inf".parse().unwrap());
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_secs(360))
+        );
+        headers.insert("retry-after", "1e400".parse().unwrap());
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            "99999999999999999999h".parse().unwrap(),
+        );
+        assert_eq!(
+            retry_after_from_headers(&headers),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(parse_reset_duration("99999999999999999999h"), None);
+    }
+}
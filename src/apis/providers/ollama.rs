@@ -0,0 +1,86 @@
+use crate::apis::providers::{read_json_response, LlmConfig, LlmProvider};
+use crate::errors::GippityError;
+use crate::models::general::llm::{LlmResponse, Message, Usage};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+// Ollama native chat API, for models hosted locally
+#[derive(Debug)]
+pub struct OllamaProvider {
+    config: LlmConfig,
+}
+
+impl OllamaProvider {
+    pub fn new(config: LlmConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.config.temperature
+    }
+
+    async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse, GippityError> {
+        let client: Client = Client::new();
+        let url: String = format!("{}/api/chat", self.config.base_url);
+
+        let request: OllamaRequest = OllamaRequest {
+            model: self.config.model.clone(),
+            messages,
+            stream: false,
+            options: OllamaOptions {
+                temperature: self.config.temperature,
+            },
+        };
+
+        let res: OllamaResponse =
+            read_json_response(client.post(url).json(&request).send().await?).await?;
+
+        if res.message.content.is_empty() {
+            return Err(GippityError::EmptyResponse);
+        }
+        Ok(LlmResponse {
+            content: res.message.content,
+            model: self.config.model.clone(),
+            usage: Some(Usage {
+                prompt_tokens: res.prompt_eval_count,
+                completion_tokens: res.eval_count,
+            }),
+        })
+    }
+}
@@ -0,0 +1,148 @@
+use crate::apis::providers::{
+    check_response, read_json_response, ChatStream, LlmConfig, LlmProvider,
+};
+use crate::apis::sse::sse_data_stream;
+use crate::errors::GippityError;
+use crate::models::general::llm::{
+    APIResponse, APIStreamChunk, ChatCompletion, ChatDelta, LlmResponse, Message,
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    Client,
+};
+use serde::Serialize;
+
+// Chat completion asking for server-sent events, with usage in the last event
+#[derive(Debug, Serialize)]
+struct StreamingChatCompletion {
+    #[serde(flatten)]
+    chat_completion: ChatCompletion,
+    stream: bool,
+    stream_options: StreamOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+// OpenAI chat completions API, also spoken by llama.cpp server and most gateways
+#[derive(Debug)]
+pub struct OpenAIProvider {
+    config: LlmConfig,
+}
+
+impl OpenAIProvider {
+    pub fn new(config: LlmConfig) -> Self {
+        Self { config }
+    }
+
+    fn build_client(&self) -> Result<Client, GippityError> {
+        // Creates headers
+        let mut headers: HeaderMap = HeaderMap::new();
+
+        // Creates API key header (local servers usually do not need one)
+        if let Some(api_key) = &self.config.api_key {
+            headers.insert(
+                "authorization",
+                HeaderValue::from_str(&format!("Bearer {}", api_key))?,
+            );
+        }
+
+        // Creates OpenAI Org header
+        if let Some(api_org) = &self.config.organization {
+            headers.insert(
+                "OpenAI-Organization",
+                HeaderValue::from_str(api_org.as_str())?,
+            );
+        }
+
+        Ok(Client::builder().default_headers(headers).build()?)
+    }
+
+    fn chat_completion(&self, messages: Vec<Message>) -> ChatCompletion {
+        ChatCompletion {
+            model: self.config.model.clone(),
+            messages,
+            temperature: self.config.temperature,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAIProvider {
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.config.temperature
+    }
+
+    async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse, GippityError> {
+        let client: Client = self.build_client()?;
+        let url: String = format!("{}/chat/completions", self.config.base_url);
+
+        // Create chat completion
+        let chat_completion: ChatCompletion = self.chat_completion(messages);
+
+        // Extracts API response
+        let res: APIResponse =
+            read_json_response(client.post(url).json(&chat_completion).send().await?).await?;
+
+        // send response
+        let content: String = res
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or(GippityError::EmptyResponse)?;
+
+        Ok(LlmResponse {
+            content,
+            model: self.config.model.clone(),
+            usage: res.usage,
+        })
+    }
+
+    fn streaming(&self) -> bool {
+        self.config.stream
+    }
+
+    async fn chat_stream(&self, messages: Vec<Message>) -> Result<ChatStream, GippityError> {
+        let client: Client = self.build_client()?;
+        let url: String = format!("{}/chat/completions", self.config.base_url);
+
+        let streaming_completion: StreamingChatCompletion = StreamingChatCompletion {
+            chat_completion: self.chat_completion(messages),
+            stream: true,
+            stream_options: StreamOptions {
+                include_usage: true,
+            },
+        };
+
+        let response: reqwest::Response =
+            check_response(client.post(url).json(&streaming_completion).send().await?).await?;
+
+        // Every event carries a piece of the first choice, the last one the usage
+        let deltas = sse_data_stream(response.bytes_stream()).map(|data| {
+            let chunk: APIStreamChunk = serde_json::from_str(&data?)?;
+            let content: String = chunk
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.delta.content)
+                .unwrap_or_default();
+
+            Ok(ChatDelta {
+                content,
+                usage: chunk.usage,
+            })
+        });
+
+        Ok(Box::pin(deltas))
+    }
+}
@@ -0,0 +1,193 @@
+use crate::apis::providers::LlmProvider;
+use crate::errors::GippityError;
+use crate::models::general::llm::{LlmResponse, Message, Usage};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::Mutex;
+
+// Key used for responses that apply to any ai_function
+pub const ANY_FUNCTION: &str = "*";
+
+// Canned outputs as read from a script file. Each ai_function maps to either
+// a single output or a list of outputs returned one after the other
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ScriptEntry {
+    Single(String),
+    Sequence(Vec<String>),
+}
+
+// Answers chat requests with canned outputs keyed by ai_function name
+#[derive(Debug, Default)]
+pub struct ScriptedProvider {
+    responses: Mutex<HashMap<String, VecDeque<String>>>,
+    // Number of requests answered per ai_function
+    calls: Mutex<HashMap<String, usize>>,
+}
+
+impl ScriptedProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds an output for the ai_function, outputs are returned in insertion order
+    pub fn with_response(self, function_name: &str, response: &str) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(function_name.to_string())
+            .or_default()
+            .push_back(response.to_string());
+        self
+    }
+
+    // Loads a JSON script such as {"print_project_scope": "{...}", "print_site_urls": ["[...]"]}
+    pub fn from_file(path: &str) -> Result<Self, GippityError> {
+        let script_str: String = fs::read_to_string(path)?;
+        let script: HashMap<String, ScriptEntry> = serde_json::from_str(&script_str)?;
+
+        let mut provider: Self = Self::new();
+        for (function_name, entry) in script {
+            let outputs: Vec<String> = match entry {
+                ScriptEntry::Single(output) => vec![output],
+                ScriptEntry::Sequence(outputs) => outputs,
+            };
+            for output in outputs {
+                provider = provider.with_response(&function_name, &output);
+            }
+        }
+
+        Ok(provider)
+    }
+
+    // Picks the next output for the messages. The last output of a sequence keeps
+    // being returned so loops calling the same ai_function never run dry
+    pub fn next_response(&self, messages: &[Message]) -> Option<String> {
+        let function_name: String =
+            function_name_from_messages(messages).unwrap_or_else(|| ANY_FUNCTION.to_string());
+        *self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(function_name.clone())
+            .or_default() += 1;
+
+        let mut responses = self.responses.lock().unwrap();
+        let outputs: &mut VecDeque<String> = if responses.contains_key(&function_name) {
+            responses.get_mut(&function_name)?
+        } else {
+            responses.get_mut(ANY_FUNCTION)?
+        };
+
+        match outputs.len() {
+            0 => None,
+            1 => outputs.front().cloned(),
+            _ => outputs.pop_front(),
+        }
+    }
+
+    // How many times the ai_function was requested so far
+    #[cfg(test)]
+    pub fn call_count(&self, function_name: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .get(function_name)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedProvider {
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    fn temperature(&self) -> f32 {
+        0.0
+    }
+
+    async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse, GippityError> {
+        let content: String = self.next_response(&messages).ok_or_else(|| {
+            GippityError::Config(format!(
+                "No scripted response for ai_function {:?}",
+                function_name_from_messages(&messages)
+            ))
+        })?;
+
+        Ok(LlmResponse {
+            usage: Some(Usage::estimate(&messages, &content)),
+            model: self.model().to_string(),
+            content,
+        })
+    }
+}
+
+// Finds the name of the ai_function that was extended into the prompt. Its signature comes
+// before the input, which may itself be code declaring functions, and long signatures
+// are printed with the name on the next line
+pub fn function_name_from_messages(messages: &[Message]) -> Option<String> {
+    messages.iter().rev().find_map(|message| {
+        let fn_start: usize = message.content.find("pub fn")? + "pub fn".len();
+        let function_name: String = message.content[fn_start..]
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+
+        if function_name.is_empty() {
+            None
+        } else {
+            Some(function_name)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_functions::aifunc_architect::print_project_scope;
+    use crate::ai_functions::aifunc_backend::print_improved_webserver_code;
+    use crate::helpers::general::extend_ai_function;
+
+    #[test]
+    fn tests_finds_function_name_in_extended_prompt() {
+        let message: Message = extend_ai_function(print_project_scope, "dummy variable");
+        assert_eq!(
+            function_name_from_messages(&[message]),
+            Some("print_project_scope".to_string())
+        );
+
+        let code_message: Message =
+            extend_ai_function(print_improved_webserver_code, "pub fn handler() {}");
+        assert_eq!(
+            function_name_from_messages(&[code_message]),
+            Some("print_improved_webserver_code".to_string())
+        );
+    }
+
+    #[test]
+    fn tests_returns_responses_in_order() {
+        let provider: ScriptedProvider = ScriptedProvider::new()
+            .with_response("print_project_scope", "first")
+            .with_response("print_project_scope", "second")
+            .with_response(ANY_FUNCTION, "fallback");
+
+        let scope_msg: Vec<Message> = vec![extend_ai_function(print_project_scope, "dummy")];
+        let other_msg: Vec<Message> = vec![Message {
+            role: "user".to_string(),
+            content: "Hi there".to_string(),
+        }];
+
+        assert_eq!(provider.next_response(&scope_msg).unwrap(), "first");
+        assert_eq!(provider.next_response(&scope_msg).unwrap(), "second");
+        assert_eq!(provider.next_response(&scope_msg).unwrap(), "second");
+        assert_eq!(provider.next_response(&other_msg).unwrap(), "fallback");
+        assert_eq!(provider.call_count("print_project_scope"), 3);
+        assert_eq!(provider.call_count("print_site_urls"), 0);
+    }
+}
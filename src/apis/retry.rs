@@ -0,0 +1,226 @@
+use crate::errors::GippityError;
+use crate::helpers::command_line::PrintCommand;
+
+use dotenv::dotenv;
+use std::env;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time;
+
+// How LLM calls are retried when they fail for a transient reason
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Fraction of the backoff randomly added or removed, between 0 and 1
+    pub jitter: f64,
+    pub attempt_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+            attempt_timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Reads LLM_RETRY_MAX_ATTEMPTS, LLM_RETRY_BASE_DELAY_MS, LLM_RETRY_MAX_DELAY_MS,
+    // LLM_RETRY_JITTER and LLM_REQUEST_TIMEOUT_SECS, falling back to the defaults
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+        let mut policy: Self = Self::default();
+
+        if let Ok(max_attempts) = env::var("LLM_RETRY_MAX_ATTEMPTS") {
+            policy.max_attempts = max_attempts.trim().parse::<u32>()?.max(1);
+        }
+        if let Ok(base_delay) = env::var("LLM_RETRY_BASE_DELAY_MS") {
+            policy.base_delay = Duration::from_millis(base_delay.trim().parse()?);
+        }
+        if let Ok(max_delay) = env::var("LLM_RETRY_MAX_DELAY_MS") {
+            policy.max_delay = Duration::from_millis(max_delay.trim().parse()?);
+        }
+        if let Ok(jitter) = env::var("LLM_RETRY_JITTER") {
+            policy.jitter = jitter.trim().parse::<f64>()?.clamp(0.0, 1.0);
+        }
+        if let Ok(attempt_timeout) = env::var("LLM_REQUEST_TIMEOUT_SECS") {
+            policy.attempt_timeout = Duration::from_secs(attempt_timeout.trim().parse()?);
+        }
+
+        Ok(policy)
+    }
+
+    // Exponential backoff for the given attempt (starting at 1), with jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent: i32 = attempt.saturating_sub(1).min(16) as i32;
+        let delay_secs: f64 =
+            (self.base_delay.as_secs_f64() * 2f64.powi(exponent)).min(self.max_delay.as_secs_f64());
+
+        let spread: f64 = delay_secs * self.jitter * (jitter_sample() * 2.0 - 1.0);
+        Duration::from_secs_f64((delay_secs + spread).max(0.0))
+    }
+
+    // Wait before the next attempt: the server's Retry-After when it sent one, the backoff
+    // otherwise, never longer than max_delay
+    pub fn retry_delay(&self, err: &GippityError, attempt: u32) -> Duration {
+        err.retry_after()
+            .map(|retry_after| retry_after.min(self.max_delay))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+
+    // Runs the operation until it succeeds, fails for good or runs out of attempts
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, GippityError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, GippityError>>,
+    {
+        let mut attempt: u32 = 1;
+
+        loop {
+            let err: GippityError = match time::timeout(self.attempt_timeout, operation()).await {
+                Ok(Ok(result)) => return Ok(result),
+                Ok(Err(e)) => e,
+                Err(_) => GippityError::Timeout(self.attempt_timeout),
+            };
+
+            if !err.is_retryable() || attempt >= self.max_attempts {
+                return Err(err);
+            }
+
+            let delay: Duration = self.retry_delay(&err, attempt);
+            let retry_msg: String = format!(
+                "{}. Retrying in {:.1}s (attempt {}/{})",
+                err,
+                delay.as_secs_f64(),
+                attempt + 1,
+                self.max_attempts
+            );
+            PrintCommand::Issue.print_agent_message("LLM Client", &retry_msg);
+
+            time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+// Number in [0, 1) good enough to spread retries of concurrent clients
+fn jitter_sample() -> f64 {
+    let nanos: u32 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    (nanos % 1_000_000) as f64 / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            jitter: 0.0,
+            attempt_timeout: Duration::from_millis(200),
+        }
+    }
+
+    #[test]
+    fn tests_backoff_grows_until_max_delay() {
+        let policy: RetryPolicy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(20), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn tests_caps_server_retry_after_at_max_delay() {
+        let policy: RetryPolicy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let rate_limited = |retry_after: Duration| GippityError::Api {
+            status: 429,
+            message: "Rate limit reached".to_string(),
+            retry_after: Some(retry_after),
+        };
+
+        assert_eq!(
+            policy.retry_delay(&rate_limited(Duration::from_secs(86_400)), 1),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            policy.retry_delay(&rate_limited(Duration::from_secs(2)), 1),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_retries_rate_limits_then_succeeds() {
+        let attempts: AtomicU32 = AtomicU32::new(0);
+
+        let res: Result<String, GippityError> = fast_policy()
+            .run(|| async {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(GippityError::Api {
+                        status: 429,
+                        message: "Rate limit reached".to_string(),
+                        retry_after: Some(Duration::from_millis(1)),
+                    });
+                }
+                Ok("answer".to_string())
+            })
+            .await;
+
+        assert_eq!(res.unwrap(), "answer");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn tests_does_not_retry_fatal_errors() {
+        let attempts: AtomicU32 = AtomicU32::new(0);
+
+        let res: Result<String, GippityError> = fast_policy()
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(GippityError::Api {
+                    status: 401,
+                    message: "Invalid API key".to_string(),
+                    retry_after: None,
+                })
+            })
+            .await;
+
+        assert!(matches!(res, Err(GippityError::Api { status: 401, .. })));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn tests_times_out_slow_attempts() {
+        let attempts: AtomicU32 = AtomicU32::new(0);
+
+        let res: Result<String, GippityError> = fast_policy()
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                time::sleep(Duration::from_secs(5)).await;
+                Ok("too late".to_string())
+            })
+            .await;
+
+        assert!(matches!(res, Err(GippityError::Timeout(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
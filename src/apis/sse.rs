@@ -0,0 +1,137 @@
+use crate::errors::GippityError;
+
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+
+// Payload that marks the end of an OpenAI style event stream
+const DONE_DATA: &str = "[DONE]";
+
+// Splits a server-sent events body into the data of each event
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    data_lines: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Feeds the next chunk of the body, returning the events it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events: Vec<String> = vec![];
+        while let Some(line_end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line_bytes: Vec<u8> = self.buffer.drain(..=line_end).collect();
+            let line: String = String::from_utf8_lossy(&line_bytes).to_string();
+            if let Some(event) = self.read_line(line.trim_end_matches(['\n', '\r'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    // Flushes an event left open when the body ends without a blank line
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut events: Vec<String> = vec![];
+        if !self.buffer.is_empty() {
+            let line: String = String::from_utf8_lossy(&self.buffer).to_string();
+            self.buffer.clear();
+            events.extend(self.read_line(line.trim_end_matches('\r')));
+        }
+        events.extend(self.read_line(""));
+        events
+    }
+
+    // A blank line dispatches the event, "data:" lines add to it, anything else is ignored
+    fn read_line(&mut self, line: &str) -> Option<String> {
+        if line.is_empty() {
+            if self.data_lines.is_empty() {
+                return None;
+            }
+            let event: String = self.data_lines.join("\n");
+            self.data_lines.clear();
+            return Some(event);
+        }
+
+        if let Some(data) = line.strip_prefix("data:") {
+            self.data_lines
+                .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+        }
+        None
+    }
+}
+
+// Turns a stream of body chunks into the data of each event, ending at [DONE]
+pub fn sse_data_stream<S, B, E>(body: S) -> impl Stream<Item = Result<String, GippityError>>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<GippityError>,
+{
+    let pending: VecDeque<String> = VecDeque::new();
+    let state = (body, SseDecoder::new(), pending, false);
+
+    stream::unfold(
+        state,
+        |(mut body, mut decoder, mut pending, mut done)| async move {
+            loop {
+                if let Some(data) = pending.pop_front() {
+                    if data == DONE_DATA {
+                        return None;
+                    }
+                    return Some((Ok(data), (body, decoder, pending, done)));
+                }
+
+                if done {
+                    return None;
+                }
+
+                match body.next().await {
+                    Some(Ok(chunk)) => pending.extend(decoder.push(chunk.as_ref())),
+                    Some(Err(e)) => {
+                        done = true;
+                        return Some((Err(e.into()), (body, decoder, pending, done)));
+                    }
+                    None => {
+                        done = true;
+                        pending.extend(decoder.finish());
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_decodes_events_split_across_chunks() {
+        let mut decoder: SseDecoder = SseDecoder::new();
+
+        assert!(decoder.push(b": keep-alive\n\ndata: {\"a\"").is_empty());
+        assert_eq!(decoder.push(b": 1}\r\n\r\ndata: x\n"), vec!["{\"a\": 1}"]);
+        assert_eq!(decoder.push(b"data: y\n\n"), vec!["x\ny"]);
+        assert!(decoder.push("data: caf\u{e9}".as_bytes()).is_empty());
+        assert_eq!(decoder.finish(), vec!["caf\u{e9}"]);
+    }
+
+    #[tokio::test]
+    async fn tests_stops_data_stream_at_done() {
+        let chunks: Vec<Result<&[u8], GippityError>> = vec![
+            Ok(b"data: one\n\nda"),
+            Ok(b"ta: two\n\ndata: [DONE]\n\n"),
+            Ok(b"data: ignored\n\n"),
+        ];
+
+        let data: Vec<String> = sse_data_stream(stream::iter(chunks))
+            .map(|data| data.unwrap())
+            .collect()
+            .await;
+        assert_eq!(data, vec!["one", "two"]);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::apis::cassette::{active_cassette, Cassette, CassetteMode};
+use crate::apis::providers::{init_provider, LlmConfig};
+use crate::errors::GippityError;
+use crate::generators::openapi::{openapi_document, render_openapi, OpenApiFormat};
+use crate::helpers::command_line::{get_user_response, set_auto_confirm, PrintCommand, Verbosity};
+use crate::helpers::workspace::Workspace;
+use crate::models::agents::agent_traits::RouteObject;
+use crate::models::agents_manager::checkpoint::RunCheckpoint;
+use crate::models::agents_manager::managing_agent::ManagingAgent;
+use crate::models::general::budget::Budget;
+use crate::models::general::usage::RunReport;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+const MANAGER_POSITION: &str = "Project Manager";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "auto_gippity",
+    version,
+    about = "Builds a web server from a description using a team of LLM agents",
+    long_about = None
+)]
+pub struct Cli {
+    // Asks for the project interactively when no command is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Model answering the agents (overrides LLM_MODEL)"
+    )]
+    pub model: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Workspace directory (overrides GIPPITY_WORKSPACE)"
+    )]
+    pub workspace: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub budget: BudgetArgs,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Run AI generated code without asking for confirmation"
+    )]
+    pub yes: bool,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Also print the full answer of every LLM call"
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only print issues"
+    )]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(about = "Starts a new run for the given project description")]
+    New {
+        prompt: String,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Record every LLM call to this cassette directory"
+        )]
+        record: Option<PathBuf>,
+    },
+    #[command(about = "Continues a run from its last checkpoint")]
+    Resume { run_dir: PathBuf },
+    #[command(about = "Prints the fact sheet and LLM usage stored in a run")]
+    Inspect { run_dir: PathBuf },
+    #[command(about = "Runs a recorded cassette again without calling the LLM")]
+    Replay { cassette: PathBuf },
+    #[command(about = "Prints the API endpoints found in a run as an OpenAPI 3.1 document")]
+    Openapi {
+        run_dir: PathBuf,
+        #[arg(long, default_value = "yaml", help = "Document format: yaml or json")]
+        format: OpenApiFormat,
+    },
+}
+
+// Run wide ceilings, overriding GIPPITY_MAX_TOKENS, GIPPITY_MAX_COST_USD and GIPPITY_MAX_CALLS
+#[derive(Debug, Args)]
+pub struct BudgetArgs {
+    #[arg(long, global = true, help = "Most tokens the run may spend")]
+    pub max_tokens: Option<u64>,
+    #[arg(long, global = true, help = "Most dollars the run may spend")]
+    pub max_cost_usd: Option<f64>,
+    #[arg(long, global = true, help = "Most LLM calls the run may make")]
+    pub max_calls: Option<u64>,
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, _) => Verbosity::Verbose,
+        }
+    }
+
+    // Runs the selected command. Failures are reported to the user before returning
+    pub async fn run(self) -> Result<(), GippityError> {
+        self.verbosity().set();
+        set_auto_confirm(self.yes);
+
+        match &self.command {
+            Some(Command::Inspect { run_dir }) => report(inspect(run_dir), "Could not inspect run"),
+
+            Some(Command::Openapi { run_dir, format }) => report(
+                export_openapi(run_dir, *format),
+                "Could not export the API endpoints",
+            ),
+
+            Some(Command::New { prompt, record }) => {
+                self.prepare()?;
+                if let Some(record_dir) = record {
+                    let cassette = Cassette::new(CassetteMode::Record, record_dir.clone()).init();
+                    report(cassette, "Could not record the run")?;
+                }
+                start_run(prompt.clone()).await
+            }
+
+            Some(Command::Resume { run_dir }) => {
+                self.prepare()?;
+                let manage_agent: ManagingAgent =
+                    report(ManagingAgent::resume(run_dir), "Could not resume the run")?;
+                execute(manage_agent).await
+            }
+
+            Some(Command::Replay { cassette }) => {
+                self.prepare()?;
+                let prompt: String = report(
+                    Cassette::new(CassetteMode::Replay, cassette.clone())
+                        .init()
+                        .and_then(|cassette| cassette.recorded_prompt()),
+                    "Could not replay the run",
+                )?;
+                start_run(prompt).await
+            }
+
+            None => {
+                self.prepare()?;
+                let user_req: String = get_user_response("What webserver are we building today?");
+                start_run(user_req).await
+            }
+        }
+    }
+
+    // Sets up the workspace, provider and budget before any agent needs them
+    fn prepare(&self) -> Result<(), GippityError> {
+        let workspace: Result<Workspace, GippityError> = match &self.workspace {
+            Some(root) => Workspace::from_root_with_env(root),
+            None => Workspace::from_env(),
+        };
+        report(
+            workspace.and_then(Workspace::init),
+            "Could not prepare the workspace",
+        )?;
+
+        let mut llm_config: LlmConfig =
+            report(LlmConfig::from_env(), "Could not configure the LLM")?;
+        if let Some(model) = &self.model {
+            llm_config.model = model.clone();
+        }
+        report(init_provider(llm_config), "Could not configure the LLM")?;
+
+        let mut budget: Budget = report(Budget::from_env(), "Could not read the budget")?;
+        if let Some(max_tokens) = self.budget.max_tokens {
+            budget.run.max_tokens = Some(max_tokens);
+        }
+        if let Some(max_cost_usd) = self.budget.max_cost_usd {
+            budget.run.max_cost_usd = Some(max_cost_usd);
+        }
+        if let Some(max_calls) = self.budget.max_calls {
+            budget.run.max_calls = Some(max_calls);
+        }
+        report(budget.init(), "Could not read the budget")?;
+
+        Ok(())
+    }
+}
+
+async fn start_run(user_req: String) -> Result<(), GippityError> {
+    // Recordings keep the request so they can be replayed later
+    if let Some(cassette) = report(active_cassette(), "Could not record the run")? {
+        report(
+            cassette.record_prompt(&user_req),
+            "Could not record the run",
+        )?;
+    }
+
+    let manage_agent: ManagingAgent = report(
+        ManagingAgent::new(user_req).await,
+        "Could not define the project",
+    )?;
+    execute(manage_agent).await
+}
+
+async fn execute(mut manage_agent: ManagingAgent) -> Result<(), GippityError> {
+    report(
+        manage_agent.execute_project().await,
+        "Project execution stopped",
+    )
+}
+
+// Prints what a run found so far and what it cost
+fn inspect(run_dir: &Path) -> Result<(), GippityError> {
+    let checkpoint: RunCheckpoint = RunCheckpoint::load(run_dir)?;
+
+    println!("{}", serde_json::to_string_pretty(&checkpoint.factsheet)?);
+    println!();
+
+    match checkpoint.agents.get(checkpoint.next_agent) {
+        Some(agent) => println!(
+            "Next agent: {} ({} of {})",
+            agent.position,
+            checkpoint.next_agent + 1,
+            checkpoint.agents.len()
+        ),
+        None if checkpoint.agents.is_empty() => println!("No agent has started yet"),
+        None => println!("All agents finished"),
+    }
+
+    println!("LLM usage:");
+    println!("{}", RunReport::from_records(&checkpoint.usage));
+    Ok(())
+}
+
+fn export_openapi(run_dir: &Path, format: OpenApiFormat) -> Result<(), GippityError> {
+    let checkpoint: RunCheckpoint = RunCheckpoint::load(run_dir)?;
+    let routes: Vec<RouteObject> = checkpoint.factsheet.api_endpoint_schema.ok_or_else(|| {
+        GippityError::Config("The run has not extracted any API endpoints yet".to_string())
+    })?;
+
+    let document: serde_json::Value =
+        openapi_document(&checkpoint.factsheet.project_description, &routes);
+    print!("{}", render_openapi(&document, format)?);
+    Ok(())
+}
+
+// Tells the user what went wrong, keeping the error for the exit status
+fn report<T>(res: Result<T, GippityError>, context: &str) -> Result<T, GippityError> {
+    if let Err(e) = &res {
+        let err_msg: String = format!("{}: {}", context, e);
+        PrintCommand::Issue.print_agent_message(MANAGER_POSITION, &err_msg);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parses_subcommands_and_flags() {
+        let cli: Cli = Cli::try_parse_from([
+            "auto_gippity",
+            "new",
+            "build me a todo app",
+            "--model",
+            "gpt-4o",
+            "--max-cost-usd",
+            "2.5",
+            "-v",
+        ])
+        .expect("Failed to parse arguments");
+
+        assert!(matches!(
+            &cli.command,
+            Some(Command::New { prompt, record: None }) if prompt == "build me a todo app"
+        ));
+        assert_eq!(cli.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(cli.budget.max_cost_usd, Some(2.5));
+        assert_eq!(cli.verbosity(), Verbosity::Verbose);
+
+        let cli: Cli =
+            Cli::try_parse_from(["auto_gippity", "-q", "resume", "workspace/runs/run-1"])
+                .expect("Failed to parse arguments");
+        assert!(matches!(cli.command, Some(Command::Resume { .. })));
+        assert_eq!(cli.verbosity(), Verbosity::Quiet);
+
+        assert!(Cli::try_parse_from(["auto_gippity", "-q", "-v", "inspect", "run"]).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::models::general::budget::BudgetBreach;
+use std::fmt;
+use std::time::Duration;
+
+// Errors surfaced while talking to the LLM, the file system or the agents
+#[derive(Debug)]
+pub enum GippityError {
+    // Request could not be sent or the response could not be read
+    Transport(reqwest::Error),
+    // Non-success HTTP status without a recognisable API error body
+    HttpStatus {
+        status: u16,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+    // Error reported by the LLM API itself
+    Api {
+        status: u16,
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    // A single attempt took longer than allowed
+    Timeout(Duration),
+    // The LLM answered without any content
+    EmptyResponse,
+    // Response could not be decoded into the expected type
+    Decode(serde_json::Error),
+    Io(std::io::Error),
+    // Missing or invalid configuration (env variables, script files, ...)
+    Config(String),
+    // An agent could not complete its work
+    Agent(String),
+    // A token, cost or call ceiling was reached
+    BudgetExceeded(BudgetBreach),
+}
+
+impl fmt::Display for GippityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(e) => write!(f, "Failed to reach LLM API: {}", e),
+            Self::HttpStatus { status, body, .. } => {
+                write!(f, "LLM API answered with status {}: {}", status, body)
+            }
+            Self::Api {
+                status, message, ..
+            } => {
+                write!(f, "LLM API error (status {}): {}", status, message)
+            }
+            Self::Timeout(timeout) => write!(f, "LLM API did not answer within {:?}", timeout),
+            Self::EmptyResponse => write!(f, "LLM API returned an empty response"),
+            Self::Decode(e) => write!(f, "Failed to decode AI response: {}", e),
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::Config(msg) => write!(f, "Configuration error: {}", msg),
+            Self::Agent(msg) => write!(f, "Agent error: {}", msg),
+            Self::BudgetExceeded(breach) => write!(f, "Budget exceeded: {}", breach),
+        }
+    }
+}
+
+impl GippityError {
+    // Whether trying the same request again may succeed (rate limits, server errors, timeouts)
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(e) => !e.is_builder() && !e.is_decode(),
+            Self::HttpStatus { status, .. } | Self::Api { status, .. } => {
+                matches!(status, 408 | 409 | 429) || *status >= 500
+            }
+            Self::Timeout(_) | Self::EmptyResponse => true,
+            _ => false,
+        }
+    }
+
+    // Delay requested by the API before trying again
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::HttpStatus { retry_after, .. } | Self::Api { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for GippityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for GippityError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for GippityError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl From<std::io::Error> for GippityError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for GippityError {
+    fn from(e: reqwest::header::InvalidHeaderValue) -> Self {
+        Self::Config(format!("Invalid header value: {}", e))
+    }
+}
+
+impl From<strum::ParseError> for GippityError {
+    fn from(e: strum::ParseError) -> Self {
+        Self::Config(e.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for GippityError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        Self::Config(e.to_string())
+    }
+}
+
+impl From<std::num::ParseFloatError> for GippityError {
+    fn from(e: std::num::ParseFloatError) -> Self {
+        Self::Config(e.to_string())
+    }
+}
+
+impl From<std::str::ParseBoolError> for GippityError {
+    fn from(e: std::str::ParseBoolError) -> Self {
+        Self::Config(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_converts_decode_errors() {
+        let decode_err: serde_json::Error =
+            serde_json::from_str::<Vec<String>>("not json").unwrap_err();
+        let err: GippityError = decode_err.into();
+
+        assert!(matches!(err, GippityError::Decode(_)));
+        assert!(err.to_string().starts_with("Failed to decode AI response"));
+    }
+
+    #[test]
+    fn tests_classifies_retryable_errors() {
+        let rate_limited: GippityError = GippityError::Api {
+            status: 429,
+            message: "Rate limit reached".to_string(),
+            retry_after: Some(Duration::from_secs(2)),
+        };
+        let unauthorized: GippityError = GippityError::Api {
+            status: 401,
+            message: "Invalid API key".to_string(),
+            retry_after: None,
+        };
+        let bad_gateway: GippityError = GippityError::HttpStatus {
+            status: 502,
+            body: "Bad Gateway".to_string(),
+            retry_after: None,
+        };
+
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(2)));
+        assert!(!unauthorized.is_retryable());
+        assert!(bad_gateway.is_retryable());
+        assert!(!GippityError::Config("missing key".to_string()).is_retryable());
+    }
+}
@@ -0,0 +1,151 @@
+pub mod openapi;
+pub mod rust_client;
+pub mod sqlite;
+pub mod typescript_client;
+
+use crate::models::agents::agent_traits::{PathSegment, RouteObject};
+use std::collections::HashSet;
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+    "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+    "type", "use", "where", "while",
+];
+
+// snake_case name of every route, e.g. GET /item/{id} becomes get_item_by_id.
+// Clashing names get a numbered suffix, so generated code never repeats one
+pub fn operation_names(routes: &[RouteObject]) -> Vec<String> {
+    let mut taken: HashSet<String> = HashSet::new();
+
+    routes
+        .iter()
+        .map(|route_object| {
+            let mut words: Vec<String> = vec![route_object.method.to_string()];
+            let mut params: Vec<String> = vec![];
+            for segment in route_object.route.segments() {
+                match segment {
+                    PathSegment::Static(text) => words.push(snake_case(text)),
+                    PathSegment::Param(name) => params.push(snake_case(name)),
+                }
+            }
+            if words.len() == 1 {
+                words.push("root".to_string());
+            }
+            if !params.is_empty() {
+                words.push(format!("by_{}", params.join("_and_")));
+            }
+
+            let name: String = words
+                .into_iter()
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<String>>()
+                .join("_");
+
+            let mut unique_name: String = name.clone();
+            let mut attempt: u32 = 2;
+            while !taken.insert(unique_name.clone()) {
+                unique_name = format!("{}_{}", name, attempt);
+                attempt += 1;
+            }
+            unique_name
+        })
+        .collect()
+}
+
+// The name itself the first time it is taken, then numbered, e.g. "Item", "Item2", "Item3"
+pub fn unique_name(taken: &mut HashSet<String>, name: &str) -> String {
+    let mut unique_name: String = name.to_string();
+    let mut attempt: u32 = 2;
+    while !taken.insert(unique_name.clone()) {
+        unique_name = format!("{}{}", name, attempt);
+        attempt += 1;
+    }
+    unique_name
+}
+
+// Lowercase words joined by underscores, e.g. "userId" and "user-id" become "user_id"
+pub fn snake_case(text: &str) -> String {
+    let mut snake: String = String::new();
+    let mut previous_lower: bool = false;
+
+    for character in text.chars() {
+        if character.is_ascii_alphanumeric() {
+            if character.is_ascii_uppercase() && previous_lower {
+                snake.push('_');
+            }
+            previous_lower = character.is_ascii_lowercase() || character.is_ascii_digit();
+            snake.push(character.to_ascii_lowercase());
+        } else {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            previous_lower = false;
+        }
+    }
+
+    snake.trim_matches('_').to_string()
+}
+
+// snake_case identifier for any name, steering clear of keywords, e.g. "type" becomes "type_"
+pub fn rust_ident(name: &str) -> String {
+    let mut ident: String = snake_case(name);
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident = format!("field_{}", ident);
+    }
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+// Capitalised words without separators, e.g. "get_item_by_id" becomes "GetItemById"
+pub fn pascal_case(text: &str) -> String {
+    snake_case(text)
+        .split('_')
+        .map(|word| {
+            let mut characters = word.chars();
+            match characters.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + characters.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_names_operations_uniquely() {
+        let routes: Vec<RouteObject> = serde_json::from_str(
+            r#"[
+                {"route": "/item/{id}", "is_route_dynamic": true, "method": "get"},
+                {"route": "/item/:id", "is_route_dynamic": true, "method": "get"},
+                {"route": "/", "is_route_dynamic": false, "method": "get"},
+                {"route": "/crypto-prices/{coinId}", "is_route_dynamic": true, "method": "put"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            operation_names(&routes),
+            vec![
+                "get_item_by_id",
+                "get_item_by_id_2",
+                "get_root",
+                "put_crypto_prices_by_coin_id"
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_numbers_taken_names() {
+        let mut taken: HashSet<String> = HashSet::new();
+        assert_eq!(unique_name(&mut taken, "Item"), "Item");
+        assert_eq!(unique_name(&mut taken, "Item"), "Item2");
+        assert_eq!(unique_name(&mut taken, "Item"), "Item3");
+        assert_eq!(unique_name(&mut taken, "ItemTags"), "ItemTags");
+    }
+}
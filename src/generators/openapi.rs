@@ -0,0 +1,238 @@
+use crate::errors::GippityError;
+use crate::generators::operation_names;
+use crate::models::agents::agent_traits::{is_id_param, RouteObject, TypeHint};
+
+use dotenv::dotenv;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+const OPENAPI_VERSION: &str = "3.1.0";
+const API_VERSION: &str = "0.1.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum OpenApiFormat {
+    Yaml,
+    Json,
+}
+
+impl OpenApiFormat {
+    // Reads GIPPITY_OPENAPI_FORMAT (yaml | json, defaults to yaml)
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        match env::var("GIPPITY_OPENAPI_FORMAT") {
+            Ok(format_str) => Ok(Self::from_str(format_str.trim())?),
+            Err(_) => Ok(Self::Yaml),
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+}
+
+// OpenAPI 3.1 document describing the routes of a generated web server
+pub fn openapi_document(project_description: &str, routes: &[RouteObject]) -> Value {
+    let mut paths: Map<String, Value> = Map::new();
+
+    for (route_object, operation_id) in routes.iter().zip(operation_names(routes)) {
+        let path: String = route_object
+            .route
+            .fill_params(|name| format!("{{{}}}", name));
+
+        let mut operation: Map<String, Value> = Map::new();
+        operation.insert("operationId".to_string(), json!(operation_id));
+
+        let parameters: Vec<Value> = route_object
+            .route
+            .params()
+            .into_iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": param_schema(name),
+                })
+            })
+            .collect();
+        if !parameters.is_empty() {
+            operation.insert("parameters".to_string(), json!(parameters));
+        }
+
+        if !route_object.request_body.is_null() {
+            operation.insert(
+                "requestBody".to_string(),
+                json!({
+                    "required": true,
+                    "content": {
+                        "application/json": { "schema": shape_schema(&route_object.request_body) }
+                    }
+                }),
+            );
+        }
+
+        let mut success: Value = json!({ "description": "Successful response" });
+        if !route_object.response.is_null() {
+            success["content"] = json!({
+                "application/json": { "schema": shape_schema(&route_object.response) }
+            });
+        }
+        let mut responses: Map<String, Value> = Map::new();
+        responses.insert("200".to_string(), success);
+        if route_object.route.is_dynamic() {
+            responses.insert("404".to_string(), json!({ "description": "Not found" }));
+        }
+        operation.insert("responses".to_string(), Value::Object(responses));
+
+        // Routes sharing a path become operations of the same path item
+        let path_item: &mut Value = paths
+            .entry(path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path_item[route_object.method.to_string()] = Value::Object(operation);
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "Generated web server",
+            "version": API_VERSION,
+            "description": project_description,
+        },
+        "paths": paths,
+    })
+}
+
+// Serializes the document in the requested format
+pub fn render_openapi(document: &Value, format: OpenApiFormat) -> Result<String, GippityError> {
+    match format {
+        OpenApiFormat::Json => Ok(serde_json::to_string_pretty(document)?),
+        OpenApiFormat::Yaml => serde_yaml::to_string(document)
+            .map_err(|e| GippityError::Config(format!("Could not write OpenAPI YAML: {}", e))),
+    }
+}
+
+// Ids are numbers in the code template, any other parameter is text
+fn param_schema(name: &str) -> Value {
+    if is_id_param(name) {
+        json!({ "type": "integer" })
+    } else {
+        json!({ "type": "string" })
+    }
+}
+
+// JSON Schema for a shape of type hints, e.g. {"id": "number"}. Values that
+// are not hints are taken as examples of their type
+pub fn shape_schema(shape: &Value) -> Value {
+    match shape {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_i64() || number.is_u64() => {
+            json!({ "type": "integer" })
+        }
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::String(hint) => match TypeHint::parse(hint) {
+            Some(TypeHint::String) => json!({ "type": "string" }),
+            Some(TypeHint::Integer) => json!({ "type": "integer" }),
+            Some(TypeHint::Number) => json!({ "type": "number" }),
+            Some(TypeHint::Boolean) => json!({ "type": "boolean" }),
+            None => json!({ "type": "string", "examples": [hint] }),
+        },
+        Value::Array(items) => match items.first() {
+            Some(item) => json!({ "type": "array", "items": shape_schema(item) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), shape_schema(value)))
+                .collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": fields.keys().collect::<Vec<&String>>(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_exports_routes_as_openapi() {
+        let routes: Vec<RouteObject> = serde_json::from_str(
+            r#"[
+                {
+                    "route": "/item/{id}",
+                    "is_route_dynamic": "true",
+                    "method": "get",
+                    "request_body": "None",
+                    "response": {"id": "number", "name": "string", "completed": "bool"}
+                },
+                {
+                    "route": "/item",
+                    "is_route_dynamic": "false",
+                    "method": "post",
+                    "request_body": {"id": "u64", "name": "string"},
+                    "response": "None"
+                },
+                {
+                    "route": "/item/{id}",
+                    "is_route_dynamic": "true",
+                    "method": "delete",
+                    "request_body": "None",
+                    "response": "None"
+                },
+                {
+                    "route": "/session/{uuid}",
+                    "is_route_dynamic": "true",
+                    "method": "get",
+                    "request_body": "None",
+                    "response": "None"
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let document: Value = openapi_document("A todo list", &routes);
+
+        assert_eq!(document["openapi"], "3.1.0");
+        let item_by_id: &Value = &document["paths"]["/item/{id}"];
+        assert_eq!(item_by_id["get"]["operationId"], "get_item_by_id");
+        assert_eq!(item_by_id["get"]["parameters"][0]["in"], "path");
+        assert_eq!(
+            item_by_id["get"]["responses"]["200"]["content"]["application/json"]["schema"]
+                ["properties"]["completed"],
+            json!({ "type": "boolean" })
+        );
+        assert!(item_by_id["delete"]["responses"]["404"].is_object());
+        assert_eq!(
+            item_by_id["get"]["parameters"][0]["schema"],
+            json!({ "type": "integer" })
+        );
+        assert_eq!(
+            document["paths"]["/session/{uuid}"]["get"]["parameters"][0]["schema"],
+            json!({ "type": "string" })
+        );
+
+        let create_schema: &Value = &document["paths"]["/item"]["post"]["requestBody"]["content"]
+            ["application/json"]["schema"];
+        assert_eq!(
+            create_schema["properties"]["id"],
+            json!({ "type": "integer" })
+        );
+        assert_eq!(create_schema["required"], json!(["id", "name"]));
+
+        let yaml: String = render_openapi(&document, OpenApiFormat::Yaml).unwrap();
+        let reparsed: Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(reparsed, document);
+    }
+}
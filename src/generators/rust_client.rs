@@ -0,0 +1,309 @@
+use crate::errors::GippityError;
+use crate::generators::{operation_names, pascal_case, rust_ident, unique_name};
+use crate::models::agents::agent_traits::{RouteObject, TypeHint};
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const CLIENT_CARGO_TOML: &str = r#"[package]
+name = "generated_client"
+version = "0.1.0"
+edition = "2021"
+
+# Standalone crate, even when the workspace sits inside another cargo workspace
+[workspace]
+
+[dependencies]
+reqwest = { version = "0.11.17", features = ["json"] }
+serde = { version = "1.0.160", features = ["derive"] }
+serde_json = "1.0.96"
+"#;
+
+// Cargo project of a reqwest client for the generated web server
+#[derive(Debug, Clone, PartialEq)]
+pub struct RustClient {
+    pub cargo_toml: String,
+    pub lib_rs: String,
+}
+
+impl RustClient {
+    // Writes Cargo.toml and src/lib.rs into the project directory
+    pub fn write(&self, project_dir: &Path) -> Result<(), GippityError> {
+        fs::create_dir_all(project_dir.join("src"))?;
+        fs::write(project_dir.join("Cargo.toml"), &self.cargo_toml)?;
+        fs::write(project_dir.join("src").join("lib.rs"), &self.lib_rs)?;
+        Ok(())
+    }
+}
+
+// One async method per route, with a struct for every object shape of the bodies
+pub fn rust_client(routes: &[RouteObject]) -> RustClient {
+    let mut structs: StructWriter = StructWriter::default();
+    let mut methods: String = String::new();
+
+    for (route_object, operation) in routes.iter().zip(operation_names(routes)) {
+        let operation_type: String = pascal_case(&operation);
+
+        let mut args: Vec<String> = vec!["&self".to_string()];
+        let mut url_args: Vec<String> = vec![];
+        for param in route_object.route.params() {
+            let arg: String = field_ident(param);
+            args.push(format!("{}: impl std::fmt::Display", arg));
+            url_args.push(arg);
+        }
+
+        let has_body: bool = !route_object.request_body.is_null();
+        if has_body {
+            let body_type: String = structs.rust_type(
+                &route_object.request_body,
+                &format!("{}Request", operation_type),
+            );
+            args.push(format!("body: &{}", body_type));
+        }
+
+        let response_type: String = if route_object.response.is_null() {
+            "()".to_string()
+        } else {
+            structs.rust_type(
+                &route_object.response,
+                &format!("{}Response", operation_type),
+            )
+        };
+
+        // Braces of the path itself are escaped, parameters become format placeholders
+        let url_format: String = route_object
+            .route
+            .fill_params(|_| "\u{1}".to_string())
+            .replace('{', "{{")
+            .replace('}', "}}")
+            .replace('\u{1}', "{}");
+
+        let _ = writeln!(
+            methods,
+            "\n    // {} {}",
+            route_object.method.to_string().to_uppercase(),
+            route_object.route
+        );
+        let _ = writeln!(
+            methods,
+            "    pub async fn {}({}) -> Result<{}, reqwest::Error> {{",
+            operation,
+            args.join(", "),
+            response_type
+        );
+        let _ = writeln!(
+            methods,
+            "        let url: String = format!({:?}, self.base_url{});",
+            format!("{{}}{}", url_format),
+            url_args
+                .iter()
+                .map(|arg| format!(", {}", arg))
+                .collect::<String>()
+        );
+        let _ = writeln!(
+            methods,
+            "        {}self\n            .http\n            .request(reqwest::Method::{}, url)",
+            if response_type == "()" {
+                ""
+            } else {
+                "let response: reqwest::Response = "
+            },
+            route_object.method.to_string().to_uppercase()
+        );
+        if has_body {
+            methods.push_str("            .json(body)\n");
+        }
+        methods.push_str(
+            "            .send()\n            .await?\n            .error_for_status()?;\n",
+        );
+        match response_type.as_str() {
+            "()" => methods.push_str("        Ok(())\n"),
+            "String" => methods.push_str("        response.text().await\n"),
+            _ => methods.push_str("        response.json().await\n"),
+        }
+        methods.push_str("    }\n");
+    }
+
+    let lib_rs: String = format!(
+        r#"// Generated from the API endpoint schema of the web server. Do not edit by hand
+#![allow(dead_code)]
+
+use serde::{{Deserialize, Serialize}};
+
+#[derive(Debug, Clone)]
+pub struct Client {{
+    base_url: String,
+    http: reqwest::Client,
+}}
+
+impl Client {{
+    // Base URL of the server without a trailing slash, e.g. http://localhost:8080
+    pub fn new(base_url: impl Into<String>) -> Self {{
+        Self {{
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }}
+    }}
+{}}}
+{}"#,
+        methods, structs.source
+    );
+
+    RustClient {
+        cargo_toml: CLIENT_CARGO_TOML.to_string(),
+        lib_rs,
+    }
+}
+
+// Collects the struct definitions needed by the client's signatures
+#[derive(Debug, Default)]
+struct StructWriter {
+    source: String,
+    taken: HashSet<String>,
+}
+
+impl StructWriter {
+    // Rust type of a shape, defining a struct named after the shape for objects
+    fn rust_type(&mut self, shape: &Value, name: &str) -> String {
+        match shape {
+            Value::Null => "serde_json::Value".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Number(number) if number.is_i64() || number.is_u64() => "i64".to_string(),
+            Value::Number(_) => "f64".to_string(),
+            Value::String(hint) => match TypeHint::parse(hint) {
+                Some(TypeHint::Integer) => "i64".to_string(),
+                Some(TypeHint::Number) => "f64".to_string(),
+                Some(TypeHint::Boolean) => "bool".to_string(),
+                Some(TypeHint::String) | None => "String".to_string(),
+            },
+            Value::Array(items) => match items.first() {
+                Some(item) => format!("Vec<{}>", self.rust_type(item, &format!("{}Item", name))),
+                None => "Vec<serde_json::Value>".to_string(),
+            },
+            Value::Object(fields) if fields.is_empty() => "serde_json::Value".to_string(),
+            Value::Object(fields) => {
+                let struct_name: String = unique_name(&mut self.taken, name);
+
+                let mut definition: String = String::new();
+                let _ = writeln!(
+                    definition,
+                    "\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct {} {{",
+                    struct_name
+                );
+                for (key, value) in fields {
+                    let field: String = field_ident(key);
+                    let field_type: String =
+                        self.rust_type(value, &format!("{}{}", struct_name, pascal_case(key)));
+                    if field != *key {
+                        let _ = writeln!(definition, "    #[serde(rename = {:?})]", key);
+                    }
+                    let _ = writeln!(definition, "    pub {}: {},", field, field_type);
+                }
+                definition.push_str("}\n");
+
+                self.source.push_str(&definition);
+                struct_name
+            }
+        }
+    }
+}
+
+// Identifier for a JSON key or path parameter, never clashing with the body argument
+fn field_ident(key: &str) -> String {
+    let ident: String = rust_ident(key);
+    if ident == "body" {
+        return "body_".to_string();
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::workspace::TempWorkspace;
+    use crate::runner::build::{BuildCommand, BuildReport, BuildRunner};
+    use crate::runner::sandbox::Sandbox;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn test_routes() -> Vec<RouteObject> {
+        serde_json::from_str(
+            r#"[
+                {
+                    "route": "/item/{id}",
+                    "is_route_dynamic": true,
+                    "method": "get",
+                    "request_body": null,
+                    "response": {"id": "number", "type": "string", "tags": [{"label": "string"}]}
+                },
+                {
+                    "route": "/item",
+                    "is_route_dynamic": false,
+                    "method": "post",
+                    "request_body": {"id": "u64", "dueDate": "string"},
+                    "response": null
+                },
+                {
+                    "route": "/items",
+                    "is_route_dynamic": false,
+                    "method": "get",
+                    "request_body": null,
+                    "response": [{"id": "number", "done": "bool"}]
+                }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tests_generates_client_methods_and_structs() {
+        let client: RustClient = rust_client(&test_routes());
+
+        assert!(client.lib_rs.contains(
+            "pub async fn get_item_by_id(&self, id: impl std::fmt::Display) -> Result<GetItemByIdResponse, reqwest::Error>"
+        ));
+        assert!(client
+            .lib_rs
+            .contains(r#"let url: String = format!("{}/item/{}", self.base_url, id);"#));
+        assert!(client.lib_rs.contains(
+            "pub async fn post_item(&self, body: &PostItemRequest) -> Result<(), reqwest::Error>"
+        ));
+        assert!(client
+            .lib_rs
+            .contains("-> Result<Vec<GetItemsResponseItem>, reqwest::Error>"));
+        assert!(client
+            .lib_rs
+            .contains("    #[serde(rename = \"type\")]\n    pub type_: String,"));
+        assert!(client
+            .lib_rs
+            .contains("    #[serde(rename = \"dueDate\")]\n    pub due_date: String,"));
+        assert!(client
+            .lib_rs
+            .contains("    pub tags: Vec<GetItemByIdResponseTagsItem>,"));
+        assert!(client.lib_rs.contains("    pub done: bool,"));
+    }
+
+    #[tokio::test]
+    #[ignore = "fetches reqwest and serde, run with --ignored"]
+    async fn tests_generated_client_compiles() {
+        let temp_project: TempWorkspace = TempWorkspace::new("rust_client");
+        let project_dir: PathBuf = temp_project.root.clone();
+        rust_client(&test_routes()).write(&project_dir).unwrap();
+
+        let build_report: BuildReport = BuildRunner::new(
+            project_dir.clone(),
+            BuildCommand::Check,
+            Duration::from_secs(300),
+            Sandbox::from_env().unwrap(),
+        )
+        .build()
+        .await
+        .expect("Failed to run cargo");
+
+        assert!(build_report.success, "{}", build_report.error_summary());
+        assert!(build_report.diagnostics.is_empty());
+    }
+}
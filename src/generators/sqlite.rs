@@ -0,0 +1,510 @@
+use crate::generators::{pascal_case, rust_ident, snake_case};
+use crate::models::agents::agent_traits::{
+    ColumnDefinition, ColumnType, DatabaseSchema, TableDefinition,
+};
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+// Migration of the backend project, read by the data-access module at compile time
+pub const MIGRATION_FILE: &str = "0001_init.sql";
+
+// Dependency line the backend project needs for the data-access module
+pub const RUSQLITE_DEPENDENCY: &str =
+    r#"rusqlite = { version = "0.29.0", features = ["bundled"] }"#;
+
+// Mistakes in a designed schema the generators cannot work around
+pub fn schema_issues(schema: &DatabaseSchema) -> Vec<String> {
+    let mut issues: Vec<String> = vec![];
+    if schema.tables.is_empty() {
+        issues.push("The schema has no tables".to_string());
+    }
+
+    let table_names: HashSet<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    let mut seen_tables: HashSet<&str> = HashSet::new();
+    let mut seen_structs: HashSet<String> = HashSet::new();
+
+    for table in &schema.tables {
+        if !is_sql_identifier(&table.name) {
+            issues.push(format!(
+                "Table name \"{}\" is not a plain identifier",
+                table.name
+            ));
+        }
+        if !seen_tables.insert(&table.name) {
+            issues.push(format!("Table {} is defined twice", table.name));
+        } else if !seen_structs.insert(struct_name(table)) {
+            issues.push(format!(
+                "Table {} has the same singular name as another table",
+                table.name
+            ));
+        }
+        if table.columns.is_empty() {
+            issues.push(format!("Table {} has no columns", table.name));
+        }
+
+        let mut seen_columns: HashSet<String> = HashSet::new();
+        for column in &table.columns {
+            if !is_sql_identifier(&column.name) {
+                issues.push(format!(
+                    "Column name \"{}.{}\" is not a plain identifier",
+                    table.name, column.name
+                ));
+            }
+            if !seen_columns.insert(rust_ident(&column.name)) {
+                issues.push(format!(
+                    "Column {}.{} is defined twice",
+                    table.name, column.name
+                ));
+            }
+            if let Some(referenced) = &column.references {
+                if !table_names.contains(referenced.as_str()) {
+                    issues.push(format!(
+                        "Column {}.{} references the unknown table {}",
+                        table.name, column.name, referenced
+                    ));
+                }
+            }
+        }
+
+        if table.columns.iter().filter(|c| c.primary_key).count() > 1 {
+            issues.push(format!(
+                "Table {} has a composite primary key, use a single id column",
+                table.name
+            ));
+        }
+    }
+
+    issues
+}
+
+// SQLite DDL creating every table. Tables without a primary key get an integer id
+pub fn sqlite_migration(schema: &DatabaseSchema) -> String {
+    let mut migration: String = String::from(
+        "-- Generated from the database schema. Do not edit by hand\nPRAGMA foreign_keys = ON;\n",
+    );
+
+    for table in &schema.tables {
+        let mut column_lines: Vec<String> = vec![];
+        for column in keyed_columns(table) {
+            let mut line: String = format!("\"{}\" {}", column.name, sql_type(column.column_type));
+            if column.primary_key {
+                line.push_str(" PRIMARY KEY");
+            } else if !column.nullable {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(referenced) = &column.references {
+                let referenced_key: String = schema
+                    .tables
+                    .iter()
+                    .find(|t| t.name == *referenced)
+                    .map(|t| primary_key(t).0)
+                    .unwrap_or_else(|| "id".to_string());
+                let _ = write!(
+                    line,
+                    " REFERENCES \"{}\" (\"{}\")",
+                    referenced, referenced_key
+                );
+            }
+            column_lines.push(line);
+        }
+
+        let _ = write!(
+            migration,
+            "\nCREATE TABLE IF NOT EXISTS \"{}\" (\n    {}\n);\n",
+            table.name,
+            column_lines.join(",\n    ")
+        );
+    }
+
+    migration
+}
+
+// rusqlite module with a struct per table and insert, get, list, update and delete functions
+pub fn data_access_module(schema: &DatabaseSchema) -> String {
+    let mut module: String = format!(
+        r#"// Generated from the database schema. Do not edit by hand
+#![allow(dead_code)]
+
+use rusqlite::{{params, Connection, OptionalExtension, Result, Row}};
+use serde::{{Deserialize, Serialize}};
+
+pub const MIGRATION: &str = include_str!("../migrations/{}");
+
+// Opens the database file and creates the missing tables
+pub fn open(path: &str) -> Result<Connection> {{
+    let connection: Connection = Connection::open(path)?;
+    connection.execute_batch(MIGRATION)?;
+    Ok(connection)
+}}
+"#,
+        MIGRATION_FILE
+    );
+
+    for table in &schema.tables {
+        module.push_str(&table_access(table));
+    }
+
+    module
+}
+
+// Columns with exactly one primary key: the marked one, else a column named id,
+// else an integer id added in front
+fn keyed_columns(table: &TableDefinition) -> Vec<ColumnDefinition> {
+    let mut columns: Vec<ColumnDefinition> = table.columns.clone();
+    if columns.iter().any(|c| c.primary_key) {
+        return columns;
+    }
+
+    match columns.iter_mut().find(|c| c.name == "id") {
+        Some(id_column) => id_column.primary_key = true,
+        None => columns.insert(
+            0,
+            ColumnDefinition {
+                name: "id".to_string(),
+                column_type: ColumnType::Integer,
+                nullable: false,
+                primary_key: true,
+                references: None,
+            },
+        ),
+    }
+    columns
+}
+
+// Primary key column of the table and its type
+fn primary_key(table: &TableDefinition) -> (String, ColumnType) {
+    keyed_columns(table)
+        .into_iter()
+        .find(|c| c.primary_key)
+        .map(|c| (c.name, c.column_type))
+        .unwrap_or_else(|| ("id".to_string(), ColumnType::Integer))
+}
+
+fn table_access(table: &TableDefinition) -> String {
+    let struct_name: String = struct_name(table);
+    let singular_name: String = singular(&table.name);
+    let row_name: String = rust_ident(&singular_name);
+    let table_sql: String = format!("\"{}\"", table.name);
+
+    let (key_name, key_type): (String, ColumnType) = primary_key(table);
+    let key_field: String = rust_ident(&key_name);
+    let key_param_type: &str = match key_type {
+        ColumnType::Text => "&str",
+        other => rust_type(other),
+    };
+
+    let columns: Vec<ColumnDefinition> = keyed_columns(table);
+    let value_columns: Vec<&ColumnDefinition> = columns.iter().filter(|c| !c.primary_key).collect();
+    // SQLite assigns integer keys itself, other keys are part of the insert
+    let insert_columns: Vec<&ColumnDefinition> = columns
+        .iter()
+        .filter(|c| !c.primary_key || key_type != ColumnType::Integer)
+        .collect();
+
+    let mut access: String = String::new();
+
+    let _ = writeln!(
+        access,
+        "\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct {} {{",
+        struct_name
+    );
+    for column in &columns {
+        let field_type: String = if column.nullable && !column.primary_key {
+            format!("Option<{}>", rust_type(column.column_type))
+        } else {
+            rust_type(column.column_type).to_string()
+        };
+        let _ = writeln!(
+            access,
+            "    pub {}: {},",
+            rust_ident(&column.name),
+            field_type
+        );
+    }
+    access.push_str("}\n");
+
+    let _ = writeln!(
+        access,
+        "\nimpl {} {{\n    fn from_row(row: &Row) -> Result<Self> {{\n        Ok(Self {{",
+        struct_name
+    );
+    for column in &columns {
+        let _ = writeln!(
+            access,
+            "            {}: row.get({:?})?,",
+            rust_ident(&column.name),
+            column.name
+        );
+    }
+    access.push_str("        })\n    }\n}\n");
+
+    // Insert
+    let insert_sql: String = if insert_columns.is_empty() {
+        format!("INSERT INTO {} DEFAULT VALUES", table_sql)
+    } else {
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table_sql,
+            quoted_names(&insert_columns),
+            placeholders(insert_columns.len(), 1)
+        )
+    };
+    let _ = writeln!(
+        access,
+        "\n// Inserts the {}, returning its rowid\npub fn insert_{}(connection: &Connection, {}: &{}) -> Result<i64> {{\n    connection.execute(\n        {:?},\n        params![{}],\n    )?;\n    Ok(connection.last_insert_rowid())\n}}",
+        singular_name,
+        row_name,
+        row_name,
+        struct_name,
+        insert_sql,
+        field_list(&row_name, &insert_columns)
+    );
+
+    // Get and list
+    let _ = writeln!(
+        access,
+        "\npub fn get_{}(connection: &Connection, {}: {}) -> Result<Option<{}>> {{\n    connection\n        .query_row(\n            {:?},\n            params![{}],\n            {}::from_row,\n        )\n        .optional()\n}}",
+        row_name,
+        key_field,
+        key_param_type,
+        struct_name,
+        format!("SELECT * FROM {} WHERE \"{}\" = ?1", table_sql, key_name),
+        key_field,
+        struct_name
+    );
+    let _ = writeln!(
+        access,
+        "\npub fn list_{}(connection: &Connection) -> Result<Vec<{}>> {{\n    let mut statement = connection.prepare({:?})?;\n    let rows = statement.query_map([], {}::from_row)?;\n    rows.collect()\n}}",
+        rust_ident(&table.name),
+        struct_name,
+        format!("SELECT * FROM {}", table_sql),
+        struct_name
+    );
+
+    // Update, only when there is something besides the key to change
+    if !value_columns.is_empty() {
+        let assignments: String = value_columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| format!("\"{}\" = ?{}", column.name, index + 1))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let _ = writeln!(
+            access,
+            "\n// Overwrites the {} with the same key, false when there is none\npub fn update_{}(connection: &Connection, {}: &{}) -> Result<bool> {{\n    let changed: usize = connection.execute(\n        {:?},\n        params![{}, {}.{}],\n    )?;\n    Ok(changed > 0)\n}}",
+            singular_name,
+            row_name,
+            row_name,
+            struct_name,
+            format!(
+                "UPDATE {} SET {} WHERE \"{}\" = ?{}",
+                table_sql,
+                assignments,
+                key_name,
+                value_columns.len() + 1
+            ),
+            field_list(&row_name, &value_columns),
+            row_name,
+            key_field
+        );
+    }
+
+    // Delete
+    let _ = writeln!(
+        access,
+        "\n// Deletes the {}, false when there is none\npub fn delete_{}(connection: &Connection, {}: {}) -> Result<bool> {{\n    let changed: usize = connection.execute(\n        {:?},\n        params![{}],\n    )?;\n    Ok(changed > 0)\n}}",
+        singular_name,
+        row_name,
+        key_field,
+        key_param_type,
+        format!("DELETE FROM {} WHERE \"{}\" = ?1", table_sql, key_name),
+        key_field
+    );
+
+    access
+}
+
+fn struct_name(table: &TableDefinition) -> String {
+    pascal_case(&singular(&table.name))
+}
+
+// Naive singular of a table name, e.g. "categories" becomes "category"
+fn singular(name: &str) -> String {
+    let name: String = snake_case(name);
+    if let Some(stem) = name.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    for suffix in ["sses", "xes", "ches", "shes"] {
+        if name.ends_with(suffix) {
+            return name[..name.len() - 2].to_string();
+        }
+    }
+    match name.strip_suffix('s') {
+        Some(stem) if stem.len() > 1 && !stem.ends_with('s') => stem.to_string(),
+        _ => name,
+    }
+}
+
+fn sql_type(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Integer | ColumnType::Boolean => "INTEGER",
+        ColumnType::Real => "REAL",
+        ColumnType::Text => "TEXT",
+    }
+}
+
+fn rust_type(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Integer => "i64",
+        ColumnType::Real => "f64",
+        ColumnType::Text => "String",
+        ColumnType::Boolean => "bool",
+    }
+}
+
+fn is_sql_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn quoted_names(columns: &[&ColumnDefinition]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("\"{}\"", column.name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn placeholders(count: usize, first: usize) -> String {
+    (first..first + count)
+        .map(|index| format!("?{}", index))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn field_list(row_name: &str, columns: &[&ColumnDefinition]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("{}.{}", row_name, rust_ident(&column.name)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::workspace::TempWorkspace;
+    use crate::runner::build::{BuildCommand, BuildReport, BuildRunner};
+    use crate::runner::sandbox::Sandbox;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn test_schema() -> DatabaseSchema {
+        serde_json::from_str(
+            r#"{
+                "tables": [
+                    {
+                        "name": "users",
+                        "columns": [
+                            {"name": "id", "type": "INTEGER", "primary_key": true},
+                            {"name": "username", "type": "varchar(255)"}
+                        ]
+                    },
+                    {
+                        "name": "categories",
+                        "columns": [{"name": "slug", "type": "text", "primary_key": true}]
+                    },
+                    {
+                        "name": "tasks",
+                        "columns": [
+                            {"name": "type", "type": "string"},
+                            {"name": "completed", "type": "bool"},
+                            {"name": "due_date", "type": "datetime", "nullable": true},
+                            {"name": "user_id", "type": "int", "references": "users"},
+                            {"name": "category", "type": "text", "references": "categories"}
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tests_generates_migration_and_checks_schema() {
+        let schema: DatabaseSchema = test_schema();
+        assert!(schema_issues(&schema).is_empty());
+
+        let migration: String = sqlite_migration(&schema);
+        assert!(migration.contains(
+            "CREATE TABLE IF NOT EXISTS \"tasks\" (\n    \"id\" INTEGER PRIMARY KEY,\n    \"type\" TEXT NOT NULL,"
+        ));
+        assert!(migration.contains("\"due_date\" TEXT,\n"));
+        assert!(
+            migration.contains("\"category\" TEXT NOT NULL REFERENCES \"categories\" (\"slug\")")
+        );
+
+        let module: String = data_access_module(&schema);
+        assert!(module.contains("pub struct Task {\n    pub id: i64,\n    pub type_: String,"));
+        assert!(module.contains("    pub due_date: Option<String>,"));
+        assert!(module.contains("pub fn get_category(connection: &Connection, slug: &str)"));
+        assert!(module.contains("pub fn list_tasks(connection: &Connection) -> Result<Vec<Task>>"));
+
+        let mut broken: DatabaseSchema = test_schema();
+        broken.tables[2].columns[3].references = Some("accounts".to_string());
+        broken.tables.push(broken.tables[0].clone());
+        assert_eq!(
+            schema_issues(&broken),
+            vec![
+                "Column tasks.user_id references the unknown table accounts",
+                "Table users is defined twice"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "fetches rusqlite and builds SQLite with a C compiler, run with --ignored"]
+    async fn tests_generated_data_access_compiles() {
+        let schema: DatabaseSchema = test_schema();
+        let temp_project: TempWorkspace = TempWorkspace::new("data_access");
+        let project_dir: PathBuf = temp_project.root.clone();
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::create_dir_all(project_dir.join("migrations")).unwrap();
+        fs::write(
+            project_dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"data_access\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n\n[dependencies]\n{}\nserde = {{ version = \"1.0.160\", features = [\"derive\"] }}\n",
+                RUSQLITE_DEPENDENCY
+            ),
+        )
+        .unwrap();
+        fs::write(project_dir.join("src").join("lib.rs"), "pub mod db;\n").unwrap();
+        fs::write(
+            project_dir.join("src").join("db.rs"),
+            data_access_module(&schema),
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("migrations").join(MIGRATION_FILE),
+            sqlite_migration(&schema),
+        )
+        .unwrap();
+
+        let build_report: BuildReport = BuildRunner::new(
+            project_dir.clone(),
+            BuildCommand::Check,
+            Duration::from_secs(300),
+            Sandbox::from_env().unwrap(),
+        )
+        .build()
+        .await
+        .expect("Failed to run cargo");
+
+        assert!(build_report.success, "{}", build_report.error_summary());
+        assert!(build_report.diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::generators::{operation_names, pascal_case, unique_name};
+use crate::models::agents::agent_traits::{RouteObject, TypeHint};
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+const TYPESCRIPT_RESERVED: [&str; 41] = [
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+];
+
+// Shared by every wrapper: sends the request, fails on non 2xx statuses and
+// decodes JSON bodies, falling back to text for plain responses
+const CLIENT_RUNTIME: &str = r#"export class ApiError extends Error {
+  readonly status: number;
+
+  constructor(status: number, message: string) {
+    super(message);
+    this.status = status;
+  }
+}
+
+async function send<T>(method: string, url: string, body?: unknown): Promise<T> {
+  const init: RequestInit = { method };
+  if (body !== undefined) {
+    init.headers = { "Content-Type": "application/json" };
+    init.body = JSON.stringify(body);
+  }
+
+  const response: Response = await fetch(url, init);
+  if (!response.ok) {
+    throw new ApiError(response.status, `${method} ${url} failed with status ${response.status}`);
+  }
+
+  const text: string = await response.text();
+  if (text === "") {
+    return undefined as T;
+  }
+  const contentType: string = response.headers.get("content-type") ?? "";
+  return (contentType.includes("json") ? JSON.parse(text) : text) as T;
+}
+"#;
+
+// TypeScript module with an interface per object shape and a typed fetch wrapper per route
+pub fn typescript_client(routes: &[RouteObject]) -> String {
+    let mut interfaces: InterfaceWriter = InterfaceWriter::default();
+    let mut methods: String = String::new();
+
+    for (route_object, operation) in routes.iter().zip(operation_names(routes)) {
+        let operation_type: String = pascal_case(&operation);
+
+        let mut args: Vec<String> = vec![];
+        let mut url_args: Vec<String> = vec![];
+        for param in route_object.route.params() {
+            let arg: String = param_ident(param);
+            args.push(format!("{}: string | number", arg));
+            url_args.push(format!("${{encodeURIComponent(String({}))}}", arg));
+        }
+
+        let has_body: bool = !route_object.request_body.is_null();
+        if has_body {
+            let body_type: String = interfaces.ts_type(
+                &route_object.request_body,
+                &format!("{}Request", operation_type),
+            );
+            args.push(format!("body: {}", body_type));
+        }
+
+        let response_type: String = if route_object.response.is_null() {
+            "void".to_string()
+        } else {
+            interfaces.ts_type(
+                &route_object.response,
+                &format!("{}Response", operation_type),
+            )
+        };
+
+        // Static parts are escaped for the template literal, parameters are interpolated
+        let mut url_template: String = String::new();
+        let escaped_path: String = route_object
+            .route
+            .fill_params(|_| "\u{1}".to_string())
+            .replace('\\', "\\\\")
+            .replace('`', "\\`")
+            .replace('$', "\\$");
+        for (index, part) in escaped_path.split('\u{1}').enumerate() {
+            if index > 0 {
+                url_template.push_str(&url_args[index - 1]);
+            }
+            url_template.push_str(part);
+        }
+
+        let method: String = route_object.method.to_string().to_uppercase();
+        let _ = writeln!(methods, "\n  // {} {}", method, route_object.route);
+        let _ = writeln!(
+            methods,
+            "  async {}({}): Promise<{}> {{",
+            camel_case(&operation),
+            args.join(", "),
+            response_type
+        );
+        let _ = writeln!(
+            methods,
+            "    return send<{}>(\"{}\", `${{this.baseUrl}}{}`{});",
+            response_type,
+            method,
+            url_template,
+            if has_body { ", body" } else { "" }
+        );
+        methods.push_str("  }\n");
+    }
+
+    format!(
+        r#"// Generated from the API endpoint schema of the web server. Do not edit by hand
+{}
+{}
+export class Client {{
+  private readonly baseUrl: string;
+
+  // Base URL of the server, e.g. http://localhost:8080
+  constructor(baseUrl: string) {{
+    this.baseUrl = baseUrl.replace(/\/+$/, "");
+  }}
+{}}}
+"#,
+        interfaces.source, CLIENT_RUNTIME, methods
+    )
+}
+
+// Collects the interface declarations needed by the wrappers' signatures
+#[derive(Debug, Default)]
+struct InterfaceWriter {
+    source: String,
+    taken: HashSet<String>,
+}
+
+impl InterfaceWriter {
+    // TypeScript type of a shape, declaring an interface named after the shape for objects
+    fn ts_type(&mut self, shape: &Value, name: &str) -> String {
+        match shape {
+            Value::Null => "unknown".to_string(),
+            Value::Bool(_) => "boolean".to_string(),
+            Value::Number(_) => "number".to_string(),
+            Value::String(hint) => match TypeHint::parse(hint) {
+                Some(TypeHint::Integer) | Some(TypeHint::Number) => "number".to_string(),
+                Some(TypeHint::Boolean) => "boolean".to_string(),
+                Some(TypeHint::String) | None => "string".to_string(),
+            },
+            Value::Array(items) => match items.first() {
+                Some(item) => format!("{}[]", self.ts_type(item, &format!("{}Item", name))),
+                None => "unknown[]".to_string(),
+            },
+            Value::Object(fields) if fields.is_empty() => "Record<string, unknown>".to_string(),
+            Value::Object(fields) => {
+                let interface_name: String = unique_name(&mut self.taken, name);
+
+                let mut declaration: String = String::new();
+                let _ = writeln!(declaration, "\nexport interface {} {{", interface_name);
+                for (key, value) in fields {
+                    let field_type: String =
+                        self.ts_type(value, &format!("{}{}", interface_name, pascal_case(key)));
+                    let _ = writeln!(declaration, "  {}: {};", property_name(key), field_type);
+                }
+                declaration.push_str("}\n");
+
+                self.source.push_str(&declaration);
+                interface_name
+            }
+        }
+    }
+}
+
+// lowerCamelCase, e.g. "get_item_by_id" becomes "getItemById"
+fn camel_case(text: &str) -> String {
+    let pascal: String = pascal_case(text);
+    let mut characters = pascal.chars();
+    match characters.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + characters.as_str(),
+        None => String::new(),
+    }
+}
+
+// Parameter name for a path parameter, steering clear of reserved words and the body argument
+fn param_ident(name: &str) -> String {
+    let mut ident: String = camel_case(name);
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident = format!("param{}", pascal_case(name));
+    }
+    if TYPESCRIPT_RESERVED.contains(&ident.as_str()) || ident == "body" {
+        ident.push('_');
+    }
+    ident
+}
+
+// JSON keys stay as they are, quoted when they are not plain identifiers
+fn property_name(key: &str) -> String {
+    let is_identifier: bool = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        format!("{:?}", key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_generates_typescript_interfaces_and_wrappers() {
+        let routes: Vec<RouteObject> = serde_json::from_str(
+            r#"[
+                {
+                    "route": "/item/{id}",
+                    "is_route_dynamic": true,
+                    "method": "get",
+                    "request_body": null,
+                    "response": {"id": "number", "due-date": "string", "tags": [{"label": "string"}]}
+                },
+                {
+                    "route": "/item",
+                    "is_route_dynamic": false,
+                    "method": "post",
+                    "request_body": {"id": "u64", "done": "bool"},
+                    "response": null
+                },
+                {
+                    "route": "/users/:userId/items",
+                    "is_route_dynamic": true,
+                    "method": "get",
+                    "request_body": null,
+                    "response": [{"id": "number"}]
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let module: String = typescript_client(&routes);
+
+        assert!(module
+            .contains("  async getItemById(id: string | number): Promise<GetItemByIdResponse> {"));
+        assert!(module.contains(
+            "    return send<GetItemByIdResponse>(\"GET\", `${this.baseUrl}/item/${encodeURIComponent(String(id))}`);"
+        ));
+        assert!(module.contains("  async postItem(body: PostItemRequest): Promise<void> {"));
+        assert!(module.contains("`${this.baseUrl}/item`, body);"));
+        assert!(module.contains(
+            "  async getUsersItemsByUserId(userId: string | number): Promise<GetUsersItemsByUserIdResponseItem[]> {"
+        ));
+        assert!(module.contains("export interface GetItemByIdResponse {\n  \"due-date\": string;\n  id: number;\n  tags: GetItemByIdResponseTagsItem[];\n}"));
+        assert!(module
+            .contains("export interface PostItemRequest {\n  done: boolean;\n  id: number;\n}"));
+    }
+}
@@ -2,7 +2,42 @@ use crossterm::{
     style::{Color, ResetColor, SetForegroundColor},
     ExecutableCommand,
 };
-use std::io::{stdin, stdout};
+use std::io::{stdin, stdout, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+// How much the agents print, shared by the whole process
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+// Set when the user agreed up front to run AI generated code (e.g. in a build pipeline)
+static AUTO_CONFIRM: AtomicBool = AtomicBool::new(false);
+
+#[derive(PartialEq, Debug, Clone, Copy, PartialOrd)]
+pub enum Verbosity {
+    // Only issues
+    Quiet,
+    Normal,
+    // Also the full answer of every LLM call
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn current() -> Self {
+        match VERBOSITY.load(Ordering::Relaxed) {
+            0 => Self::Quiet,
+            1 => Self::Normal,
+            _ => Self::Verbose,
+        }
+    }
+
+    pub fn set(self) {
+        VERBOSITY.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+// Makes confirm_safe_code agree without asking
+pub fn set_auto_confirm(auto_confirm: bool) {
+    AUTO_CONFIRM.store(auto_confirm, Ordering::Relaxed);
+}
 
 #[derive(PartialEq, Debug)]
 pub enum PrintCommand {
@@ -12,15 +47,27 @@ pub enum PrintCommand {
 }
 
 impl PrintCommand {
+    fn statement_color(&self) -> Color {
+        match self {
+            Self::AICall => Color::Cyan,
+            Self::UnitTest => Color::Magenta,
+            Self::Issue => Color::Red,
+        }
+    }
+
+    // Issues are always shown, progress messages only when not quiet
+    fn is_shown(&self) -> bool {
+        *self == Self::Issue || Verbosity::current() > Verbosity::Quiet
+    }
+
     pub fn print_agent_message(&self, agent_pos: &str, agent_statement: &str) {
+        if !self.is_shown() {
+            return;
+        }
         let mut stdout: std::io::Stdout = stdout();
 
         // Decides on the print color
-        let statement_color: Color = match self {
-            Self::AICall => Color::Cyan,
-            Self::UnitTest => Color::Magenta,
-            Self::Issue => Color::Red,
-        };
+        let statement_color: Color = self.statement_color();
 
         // Prints the agent statement
         stdout.execute(SetForegroundColor(Color::Green)).unwrap();
@@ -33,6 +80,23 @@ impl PrintCommand {
         // Resets color
         stdout.execute(ResetColor).unwrap();
     }
+
+    // Prints the next piece of a streamed answer on the current line
+    pub fn print_stream_delta(&self, delta: &str) {
+        if !self.is_shown() {
+            return;
+        }
+        let mut stdout: std::io::Stdout = stdout();
+
+        stdout
+            .execute(SetForegroundColor(self.statement_color()))
+            .unwrap();
+        print!("{}", delta);
+        stdout.execute(ResetColor).unwrap();
+
+        // Shows the tokens now rather than when the line ends
+        stdout.flush().unwrap();
+    }
 }
 
 // Gets user request
@@ -53,7 +117,55 @@ pub fn get_user_response(question: &str) -> String {
         .expect("Failed to read response");
 
     // Trim whitespaces and return response
-    return user_response.trim().to_string();
+    user_response.trim().to_string()
+}
+
+// Asks the user whether the AI generated code is safe to execute
+pub fn confirm_safe_code() -> bool {
+    if AUTO_CONFIRM.load(Ordering::Relaxed) {
+        PrintCommand::Issue.print_agent_message(
+            "Project Manager",
+            "Running AI generated code without asking, as requested",
+        );
+        return true;
+    }
+
+    let mut stdout: std::io::Stdout = stdout();
+
+    loop {
+        // Prints the question in a specific color
+        stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
+        println!();
+        print!("WARNING: You are about to run code written entirely by AI. ");
+        println!("Review your code and confirm you wish to continue.");
+
+        // Reset color
+        stdout.execute(ResetColor).unwrap();
+
+        // Presents the options
+        stdout.execute(SetForegroundColor(Color::Green)).unwrap();
+        println!("[1] All good");
+        stdout.execute(SetForegroundColor(Color::DarkRed)).unwrap();
+        println!("[2] Lets stop this project");
+        stdout.execute(ResetColor).unwrap();
+
+        // Read user input
+        let mut human_response: String = String::new();
+        let bytes_read: usize = stdin()
+            .read_line(&mut human_response)
+            .expect("Failed to read response");
+
+        // Nobody is there to confirm (i.e. stdin was closed)
+        if bytes_read == 0 {
+            return false;
+        }
+
+        match human_response.trim().to_lowercase().as_str() {
+            "1" | "ok" | "y" => return true,
+            "2" | "no" | "n" => return false,
+            _ => println!("Invalid input. Please select '1' or '2'"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,28 @@
+use crate::errors::GippityError;
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 
+use crate::apis::cassette::active_cassette;
+use crate::generators::openapi::OpenApiFormat;
+use crate::generators::rust_client::RustClient;
+use crate::generators::sqlite::RUSQLITE_DEPENDENCY;
+use crate::models::agent_basic::basic_agent::BasicAgent;
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::general::budget::Budget;
+use crate::models::general::llm::{LlmResponse, Usage};
+use crate::models::general::usage::{record_usage, RunReport, UsageRecord};
 use crate::{apis::call_request::call_gpt, models::general::llm::Message};
 
 use super::command_line::PrintCommand;
+use super::json_repair::decode_lenient;
+use super::memory::fit_conversation;
+use super::workspace::Workspace;
+use crate::ai_functions::aifunc_general::print_repaired_json;
+use std::env;
 use std::fs;
+use std::path::PathBuf;
 
-const CODE_TEMPLATE_PATH: &str =
-    "/Users/caique/Documents/codes/rust/autogpt-course/web_template/src/code_template.rs";
-const EXECUTE_MAIN_PATH: &str =
-    "/Users/caique/Documents/codes/rust/autogpt-course/web_template/src/main.rs";
-const API_SCHEMA_PATH: &str =
-    "/Users/caique/Documents/codes/rust/autogpt-course/auto_gippity/schemas/api_schema.json";
+const DEFAULT_JSON_REPAIR_ROUNDS: u8 = 2;
 
 // Extends AI function to encourage certain specific output
 pub fn extend_ai_function(ai_func: fn(&str) -> &'static str, func_input: &str) -> Message {
@@ -34,77 +45,230 @@ pub fn extend_ai_function(ai_func: fn(&str) -> &'static str, func_input: &str) -
     }
 }
 
-// Performs call to LLM GPT
+// Performs call to LLM GPT on behalf of the agent, keeping the exchange in its memory
 pub async fn ai_task_request(
     msg_context: String,
-    agent_position: &str,
+    agent: &mut BasicAgent,
     agent_operation: &str,
     function_pass: for<'a> fn(&'a str) -> &'static str,
-) -> String {
+) -> Result<String, GippityError> {
+    let agent_position: String = agent.get_position().clone();
+
     // Extends AI function
     let extended_msg: Message = extend_ai_function(function_pass, &msg_context);
 
+    // Sends earlier turns along when configured, condensing them if they outgrow the context
+    let messages: Vec<Message> = fit_conversation(agent, extended_msg.clone()).await?;
+    let llm_response: String = request_llm(messages, &agent_position, agent_operation).await?;
+
+    // Remembers the turn so later calls can build on it
+    agent.add_memory(Message {
+        role: "user".to_string(),
+        content: extended_msg.content,
+    });
+    agent.add_memory(Message {
+        role: "assistant".to_string(),
+        content: llm_response.clone(),
+    });
+
+    Ok(llm_response)
+}
+
+// Sends the messages on behalf of the agent within its budget and records what it cost
+pub async fn request_llm(
+    messages: Vec<Message>,
+    agent_position: &str,
+    agent_operation: &str,
+) -> Result<String, GippityError> {
+    // Stops before spending more than the budget allows
+    Budget::current()?.check(&RunReport::current(), agent_position)?;
+
     // Prints current status
     PrintCommand::AICall.print_agent_message(agent_position, agent_operation);
 
-    // Gets LLM response
-    let llm_response_res = call_gpt(vec![extended_msg.clone()]).await;
+    // Gets LLM response, call_gpt takes care of retrying transient failures
+    let llm_response: LlmResponse = call_gpt(messages.clone()).await?;
 
-    // Handles success or try again
-    let llm_response: String = match llm_response_res {
-        Ok(llm_resp) => llm_resp,
-        Err(_) => call_gpt(vec![extended_msg.clone()])
-            .await
-            .expect("Failed twice to call OpenAI"),
-    };
+    // Attributes the tokens spent to the agent and ai_function
+    let usage: Usage = llm_response
+        .usage
+        .unwrap_or_else(|| Usage::estimate(&messages, &llm_response.content));
+    record_usage(UsageRecord::new(
+        agent_position,
+        agent_operation,
+        &llm_response.model,
+        usage,
+    ));
 
-    return llm_response;
+    Ok(llm_response.content)
 }
 
 // Performs call to LLM GPT - Decoded
 pub async fn ai_task_request_decoded<T: DeserializeOwned>(
     msg_context: String,
-    agent_position: &str,
+    agent: &mut BasicAgent,
     agent_operation: &str,
     function_pass: for<'a> fn(&'a str) -> &'static str,
-) -> T {
-    let llm_response: String =
-        ai_task_request(msg_context, agent_position, agent_operation, function_pass).await;
+) -> Result<T, GippityError> {
+    let mut llm_response: String =
+        ai_task_request(msg_context, agent, agent_operation, function_pass).await?;
+
+    let repair_rounds: u8 = json_repair_rounds()?;
+    let mut repair_round: u8 = 0;
+
+    loop {
+        let decode_err: serde_json::Error = match decode_lenient::<T>(&llm_response) {
+            Ok(decoded_response) => return Ok(decoded_response),
+            Err(e) => e,
+        };
+
+        if repair_round >= repair_rounds {
+            return Err(GippityError::Decode(decode_err));
+        }
+        repair_round += 1;
 
-    let decoded_response: T = serde_json::from_str(llm_response.as_str())
-        .expect("Failed to decode AI response from serde_json");
+        // Asks the model to repair its own output
+        let repair_msg: String = format!(
+            "Response from {} is not valid JSON ({}). Repair round {}/{}",
+            agent_operation, decode_err, repair_round, repair_rounds
+        );
+        PrintCommand::Issue.print_agent_message(agent.get_position(), &repair_msg);
 
-    return decoded_response;
+        let repair_context: String = format!(
+            "BROKEN_JSON: {} \n DECODE_ERROR: {} \n",
+            llm_response, decode_err
+        );
+        llm_response = ai_task_request(
+            repair_context,
+            agent,
+            get_function_string!(print_repaired_json),
+            print_repaired_json,
+        )
+        .await?;
+    }
+}
+
+// Number of times a model is asked to repair JSON it could not decode (LLM_JSON_REPAIR_ROUNDS)
+fn json_repair_rounds() -> Result<u8, GippityError> {
+    match env::var("LLM_JSON_REPAIR_ROUNDS") {
+        Ok(rounds_str) => Ok(rounds_str.trim().parse()?),
+        Err(_) => Ok(DEFAULT_JSON_REPAIR_ROUNDS),
+    }
 }
 
 // Check whether request URL is valid
-pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwest::Error> {
-    let response: reqwest::Response = client.get(url).send().await?;
-    Ok(response.status().as_u16())
+pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, GippityError> {
+    let request_status = async {
+        let response: reqwest::Response = client.get(url).send().await?;
+        Ok(response.status().as_u16())
+    };
+
+    // Records or replays the check when a cassette is in use
+    match active_cassette()? {
+        Some(cassette) => cassette.status_code(url, request_status).await,
+        None => request_status.await,
+    }
 }
 
 // Get code template
-pub fn read_code_template_contents() -> String {
-    let path: String = String::from(CODE_TEMPLATE_PATH);
-    fs::read_to_string(path).expect("Failed to read code template")
+pub fn read_code_template_contents() -> Result<String, GippityError> {
+    let path: PathBuf = Workspace::current()?.code_template_path();
+    Ok(fs::read_to_string(path)?)
+}
+
+// Get the backend code currently saved in the web server project
+pub fn read_exec_main_contents() -> Result<String, GippityError> {
+    let path: PathBuf = Workspace::current()?.exec_main_path();
+    Ok(fs::read_to_string(path)?)
 }
 
 // Save new backend code
-pub fn save_backend_code(contents: &str) {
-    let path: String = String::from(EXECUTE_MAIN_PATH);
-    fs::write(path, contents).expect("Failed write main.rs file");
+pub fn save_backend_code(contents: &str) -> Result<(), GippityError> {
+    let path: PathBuf = Workspace::current()?.exec_main_path();
+    Ok(fs::write(path, contents)?)
 }
 
 // Save the JSON API endpoint schema
-pub fn save_api_endpoints(api_endpoints: &str) {
-    let path: String = String::from(API_SCHEMA_PATH);
-    fs::write(path, api_endpoints).expect("Failed write main.rs file");
+pub fn save_api_endpoints(api_endpoints: &str) -> Result<(), GippityError> {
+    let path: PathBuf = Workspace::current()?.api_schema_path();
+    Ok(fs::write(path, api_endpoints)?)
+}
+
+// Save the SQLite migration and data-access module into the backend project,
+// adding rusqlite to the project's dependencies when it is missing
+pub fn save_database_code(migration: &str, data_access: &str) -> Result<(), GippityError> {
+    let workspace: &Workspace = Workspace::current()?;
+
+    let migration_path: PathBuf = workspace.migration_path();
+    if let Some(migrations_dir) = migration_path.parent() {
+        fs::create_dir_all(migrations_dir)?;
+    }
+    fs::write(migration_path, migration)?;
+    fs::write(workspace.database_module_path(), data_access)?;
+
+    let cargo_path: PathBuf = workspace.output_dir.join("Cargo.toml");
+    let cargo_toml: String = fs::read_to_string(&cargo_path)?;
+    if !cargo_toml
+        .lines()
+        .any(|line| line.trim_start().starts_with("rusqlite"))
+    {
+        let with_rusqlite: String = match cargo_toml.find("[dependencies]\n") {
+            Some(start) => {
+                let (head, tail) = cargo_toml.split_at(start + "[dependencies]\n".len());
+                format!("{}{}\n{}", head, RUSQLITE_DEPENDENCY, tail)
+            }
+            None => format!("{}\n[dependencies]\n{}\n", cargo_toml, RUSQLITE_DEPENDENCY),
+        };
+        fs::write(cargo_path, with_rusqlite)?;
+    }
+
+    Ok(())
+}
+
+// Save the API endpoints as an OpenAPI document
+pub fn save_openapi_document(contents: &str, format: OpenApiFormat) -> Result<(), GippityError> {
+    let path: PathBuf = Workspace::current()?.openapi_path(format);
+    Ok(fs::write(path, contents)?)
+}
+
+// Save the generated Rust client crate, returning its project directory
+pub fn save_rust_client(client: &RustClient) -> Result<PathBuf, GippityError> {
+    let project_dir: PathBuf = Workspace::current()?.rust_client_dir();
+    client.write(&project_dir)?;
+    Ok(project_dir)
+}
+
+// Save the frontend's index.html
+pub fn save_frontend_code(contents: &str) -> Result<(), GippityError> {
+    let path: PathBuf = Workspace::current()?.frontend_index_path();
+    if let Some(frontend_dir) = path.parent() {
+        fs::create_dir_all(frontend_dir)?;
+    }
+    Ok(fs::write(path, contents)?)
+}
+
+// Save the generated TypeScript client module
+pub fn save_typescript_client(contents: &str) -> Result<(), GippityError> {
+    let path: PathBuf = Workspace::current()?.typescript_client_path();
+    if let Some(client_dir) = path.parent() {
+        fs::create_dir_all(client_dir)?;
+    }
+    Ok(fs::write(path, contents)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ai_functions::aifunc_architect::print_site_urls;
     use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+    use crate::apis::providers::scripted::ScriptedProvider;
+    use crate::apis::providers::with_provider;
+    use crate::models::general::usage::usage_records;
+    use std::sync::Arc;
+
+    fn test_agent(position: &str) -> BasicAgent {
+        BasicAgent::new("Tests the LLM helpers".to_string(), position.to_string())
+    }
 
     #[test]
     fn tests_extend_ai_function() {
@@ -117,14 +281,125 @@ mod tests {
     async fn tests_ai_task_request() {
         let ai_func_param = "Build me a webserver for making stock price API requests.".to_string();
 
-        let res = ai_task_request(
-            ai_func_param,
-            "Managing Agent",
-            "Defining user requirements",
-            convert_user_input_to_goal,
+        let provider = Arc::new(ScriptedProvider::new().with_response(
+            "convert_user_input_to_goal",
+            "build a website that serves stock prices through an API",
+        ));
+
+        let res = with_provider(
+            provider,
+            ai_task_request(
+                ai_func_param,
+                &mut test_agent("Managing Agent"),
+                "Defining user requirements",
+                convert_user_input_to_goal,
+            ),
         )
-        .await;
+        .await
+        .expect("Failed to call LLM");
 
         assert!(res.len() > 20);
     }
+
+    #[tokio::test]
+    async fn tests_ai_task_request_records_usage() {
+        let provider = Arc::new(
+            ScriptedProvider::new().with_response("convert_user_input_to_goal", "build a website"),
+        );
+
+        with_provider(
+            provider,
+            ai_task_request(
+                "Build me a website".to_string(),
+                &mut test_agent("Usage Tracking Agent"),
+                get_function_string!(convert_user_input_to_goal),
+                convert_user_input_to_goal,
+            ),
+        )
+        .await
+        .expect("Failed to call LLM");
+
+        let records: Vec<UsageRecord> = usage_records()
+            .into_iter()
+            .filter(|record| record.agent_position == "Usage Tracking Agent")
+            .collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].function_name, "convert_user_input_to_goal");
+        assert!(records[0].usage.prompt_tokens > 0);
+    }
+
+    #[tokio::test]
+    async fn tests_ai_task_request_decoded_repairs_json() {
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response(
+                    "print_site_urls",
+                    "```json\n[\"https://api.binance.com\"\n\"oops",
+                )
+                .with_response("print_repaired_json", "[\"https://api.binance.com\"]"),
+        );
+
+        let res: Vec<String> = with_provider(
+            provider,
+            ai_task_request_decoded::<Vec<String>>(
+                "Build me a crypto price tracker".to_string(),
+                &mut test_agent("Solutions Architect"),
+                "print_site_urls",
+                print_site_urls,
+            ),
+        )
+        .await
+        .expect("Failed to repair JSON");
+
+        assert_eq!(res, vec!["https://api.binance.com"]);
+    }
+
+    #[tokio::test]
+    async fn tests_ai_task_request_decoded_returns_decode_error() {
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response("convert_user_input_to_goal", "not json at all")
+                .with_response("print_repaired_json", "still not json"),
+        );
+
+        let res: Result<Vec<String>, GippityError> = with_provider(
+            provider,
+            ai_task_request_decoded::<Vec<String>>(
+                "Build me a todo app".to_string(),
+                &mut test_agent("Managing Agent"),
+                "Defining user requirements",
+                convert_user_input_to_goal,
+            ),
+        )
+        .await;
+
+        assert!(matches!(res, Err(GippityError::Decode(_))));
+    }
+
+    #[tokio::test]
+    async fn tests_ai_task_request_remembers_turns() {
+        let provider = Arc::new(
+            ScriptedProvider::new().with_response("convert_user_input_to_goal", "build a website"),
+        );
+        let mut agent: BasicAgent = test_agent("Memory Agent");
+
+        with_provider(
+            provider,
+            ai_task_request(
+                "Build me a website".to_string(),
+                &mut agent,
+                get_function_string!(convert_user_input_to_goal),
+                convert_user_input_to_goal,
+            ),
+        )
+        .await
+        .expect("Failed to call LLM");
+
+        let memory: &Vec<Message> = agent.get_memory();
+        assert_eq!(memory.len(), 2);
+        assert_eq!(memory[0].role, "user");
+        assert!(memory[0].content.contains("Build me a website"));
+        assert_eq!(memory[1].role, "assistant");
+        assert_eq!(memory[1].content, "build a website");
+    }
 }
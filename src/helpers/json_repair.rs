@@ -0,0 +1,177 @@
+use serde::de::DeserializeOwned;
+
+// Removes markdown fences and any chatter around the JSON value
+pub fn strip_code_fences(raw: &str) -> &str {
+    let trimmed: &str = raw.trim();
+
+    let start: Option<usize> = trimmed.find(['{', '[']);
+    let end: Option<usize> = trimmed.rfind(['}', ']']);
+
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    }
+}
+
+// Rewrites almost-JSON into JSON: drops comments and trailing commas and adds
+// the commas models tend to forget between object members written on new lines
+pub fn make_lenient(raw: &str) -> String {
+    let without_comments: Vec<char> = strip_comments(raw).chars().collect();
+    let chars: &[char] = &without_comments;
+    let mut output: String = String::with_capacity(chars.len());
+    let mut in_string: bool = false;
+    let mut i: usize = 0;
+
+    while i < chars.len() {
+        let c: char = chars[i];
+
+        if in_string {
+            output.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                output.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == ',' && matches!(next_significant(chars, i + 1), Some('}') | Some(']')) {
+            // Trailing comma, skipped
+        } else if c == '"' {
+            if needs_comma(&output, &chars[..i]) {
+                output.push(',');
+            }
+            in_string = true;
+            output.push(c);
+        } else {
+            output.push(c);
+        }
+
+        i += 1;
+    }
+
+    output
+}
+
+// Removes line comments (including "... // etc" placeholders) and block comments
+fn strip_comments(raw: &str) -> String {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut output: String = String::with_capacity(raw.len());
+    let mut in_string: bool = false;
+    let mut i: usize = 0;
+
+    while i < chars.len() {
+        let c: char = chars[i];
+        let next: Option<&char> = chars.get(i + 1);
+
+        if in_string {
+            output.push(c);
+            if c == '\\' && next.is_some() {
+                output.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '/' && next == Some(&'/') {
+            while i + 1 < chars.len() && chars[i + 1] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some(&'*') {
+            i += 2;
+            while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                i += 1;
+            }
+            i += 1;
+        } else {
+            if c == '"' {
+                in_string = true;
+            }
+            output.push(c);
+        }
+
+        i += 1;
+    }
+
+    output
+}
+
+// Decodes strictly first, then from the lenient rewrite
+pub fn decode_lenient<T: DeserializeOwned>(raw: &str) -> Result<T, serde_json::Error> {
+    let stripped: &str = strip_code_fences(raw);
+
+    match serde_json::from_str::<T>(stripped) {
+        Ok(decoded) => Ok(decoded),
+        Err(strict_err) => {
+            serde_json::from_str::<T>(&make_lenient(stripped)).map_err(|_| strict_err)
+        }
+    }
+}
+
+fn next_significant(chars: &[char], from: usize) -> Option<char> {
+    chars[from.min(chars.len())..]
+        .iter()
+        .copied()
+        .find(|c| !c.is_whitespace())
+}
+
+// A string opening on a new line right after a complete value starts a new member
+fn needs_comma(output: &str, before: &[char]) -> bool {
+    let crossed_newline: bool = before
+        .iter()
+        .rev()
+        .take_while(|c| c.is_whitespace())
+        .any(|c| *c == '\n');
+
+    let last_char: Option<char> = output.trim_end().chars().last();
+    let ends_value: bool = match last_char {
+        Some(c) => c == '"' || c == '}' || c == ']' || c.is_ascii_alphanumeric(),
+        None => false,
+    };
+
+    crossed_newline && ends_value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agents::agent_traits::ProjectScope;
+
+    #[test]
+    fn tests_strips_markdown_fences() {
+        let raw: &str = "Here you go:\n```json\n[\"https://api.binance.com\"]\n```";
+        assert_eq!(strip_code_fences(raw), "[\"https://api.binance.com\"]");
+    }
+
+    #[test]
+    fn tests_decodes_json_with_comments_and_trailing_commas() {
+        let raw: &str = r#"[
+          {
+            "route": "/item/{id}", // the item route
+            "response": {
+              "id": "number",
+              "name": "string",
+            },
+          },
+          /* ... */
+        ]"#;
+
+        let decoded: serde_json::Value = decode_lenient(raw).unwrap();
+        assert_eq!(decoded[0]["route"], "/item/{id}");
+        assert_eq!(decoded[0]["response"]["name"], "string");
+    }
+
+    #[test]
+    fn tests_adds_missing_commas_between_members() {
+        let raw: &str = "{\n  \"is_crud_required\": true\n  \"is_user_login_and_logout\": false\n  \"is_external_urls_required\": true\n}";
+
+        let decoded: ProjectScope = decode_lenient(raw).unwrap();
+        assert!(decoded.is_crud_required);
+        assert!(!decoded.is_user_login_and_logout);
+    }
+
+    #[test]
+    fn tests_keeps_slashes_inside_strings() {
+        let raw: &str = r#"["https://api.binance.com/api/v3/exchangeInfo",]"#;
+
+        let decoded: Vec<String> = decode_lenient(raw).unwrap();
+        assert_eq!(decoded, vec!["https://api.binance.com/api/v3/exchangeInfo"]);
+    }
+}
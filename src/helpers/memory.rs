@@ -0,0 +1,268 @@
+use crate::ai_functions::aifunc_general::print_memory_summary;
+use crate::apis::providers::active_provider;
+use crate::errors::GippityError;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{extend_ai_function, request_llm};
+use crate::models::agent_basic::basic_agent::BasicAgent;
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::general::llm::{estimate_messages_tokens, Message};
+
+use std::env;
+
+const DEFAULT_MEMORY_WINDOW: usize = 0;
+
+// Context window of models missing from the table below
+const DEFAULT_CONTEXT_TOKENS: u64 = 8_192;
+
+// Room kept for the answer, same default as LLM_MAX_TOKENS
+const DEFAULT_COMPLETION_TOKENS: u64 = 4_096;
+
+// Turns kept word for word when the older ones are summarized
+const KEPT_TURNS: usize = 1;
+
+// Marks the message holding the condensed earlier turns
+const SUMMARY_PREFIX: &str = "SUMMARY OF EARLIER WORK:";
+
+// Context window in tokens. Longest matching model prefix wins
+const MODEL_CONTEXT_TOKENS: [(&str, u64); 10] = [
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("claude", 200_000),
+    ("llama3", 8_192),
+    ("llama3.1", 128_000),
+    ("scripted", 1_000_000),
+];
+
+// Number of earlier turns sent along with each prompt (LLM_MEMORY_WINDOW)
+pub fn memory_window() -> Result<usize, GippityError> {
+    match env::var("LLM_MEMORY_WINDOW") {
+        Ok(window_str) => Ok(window_str.trim().parse()?),
+        Err(_) => Ok(DEFAULT_MEMORY_WINDOW),
+    }
+}
+
+// Tokens a prompt may take: the context window (LLM_CONTEXT_TOKENS or the model's)
+// minus the room kept for the answer (LLM_MAX_TOKENS)
+pub fn prompt_token_limit(model: &str) -> Result<u64, GippityError> {
+    let context_tokens: u64 = match env::var("LLM_CONTEXT_TOKENS") {
+        Ok(context_str) => context_str.trim().parse()?,
+        Err(_) => model_context_tokens(model),
+    };
+    let completion_tokens: u64 = match env::var("LLM_MAX_TOKENS") {
+        Ok(max_tokens_str) => max_tokens_str.trim().parse()?,
+        Err(_) => DEFAULT_COMPLETION_TOKENS,
+    };
+
+    Ok(context_tokens.saturating_sub(completion_tokens))
+}
+
+// Context window of the model from the table, regardless of LLM_CONTEXT_TOKENS
+fn model_context_tokens(model: &str) -> u64 {
+    MODEL_CONTEXT_TOKENS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, context_tokens)| *context_tokens)
+        .unwrap_or(DEFAULT_CONTEXT_TOKENS)
+}
+
+// The prompt alone, or the last turns of the agent's memory followed by the prompt
+pub fn conversation_messages(agent: &BasicAgent, prompt: Message, window: usize) -> Vec<Message> {
+    let memory: &Vec<Message> = agent.get_memory();
+    if window == 0 || memory.is_empty() {
+        return vec![prompt];
+    }
+
+    // Every turn is a prompt and its reply
+    let history_start: usize = memory.len().saturating_sub(window * 2);
+
+    let mut messages: Vec<Message> = vec![Message {
+        role: "system".to_string(),
+        content: format!(
+            "You are the {} of a team building a website. Your objective: {}. \
+            Your earlier prompts and answers follow, use them to improve on your previous work.",
+            agent.get_position(),
+            agent.get_objective()
+        ),
+    }];
+
+    // The summary of older turns stays in view even when it falls outside the window
+    if history_start > 0 && memory[0].content.starts_with(SUMMARY_PREFIX) {
+        messages.push(memory[0].clone());
+    }
+
+    messages.extend(memory[history_start..].iter().cloned());
+    messages.push(Message {
+        role: "user".to_string(),
+        content: prompt.content,
+    });
+    messages
+}
+
+// Messages for the next call, condensing the agent's memory first if they would not fit
+pub async fn fit_conversation(
+    agent: &mut BasicAgent,
+    prompt: Message,
+) -> Result<Vec<Message>, GippityError> {
+    let window: usize = memory_window()?;
+    if window == 0 {
+        return Ok(vec![prompt]);
+    }
+
+    let token_limit: u64 = prompt_token_limit(active_provider()?.model())?;
+    fit_conversation_within(agent, prompt, window, token_limit).await
+}
+
+async fn fit_conversation_within(
+    agent: &mut BasicAgent,
+    prompt: Message,
+    window: usize,
+    token_limit: u64,
+) -> Result<Vec<Message>, GippityError> {
+    let messages: Vec<Message> = conversation_messages(agent, prompt.clone(), window);
+    if estimate_messages_tokens(&messages) <= token_limit {
+        return Ok(messages);
+    }
+
+    if compact_memory(agent, token_limit).await? {
+        let messages: Vec<Message> = conversation_messages(agent, prompt.clone(), window);
+        if estimate_messages_tokens(&messages) <= token_limit {
+            return Ok(messages);
+        }
+    }
+
+    PrintCommand::Issue.print_agent_message(
+        agent.get_position(),
+        "Earlier turns do not fit the context window, sending the prompt on its own",
+    );
+    Ok(vec![prompt])
+}
+
+// Replaces all but the last turns of the agent's memory with an LLM written summary
+pub async fn compact_memory(
+    agent: &mut BasicAgent,
+    token_limit: u64,
+) -> Result<bool, GippityError> {
+    let kept_start: usize = agent.get_memory().len().saturating_sub(KEPT_TURNS * 2);
+    if kept_start == 0 {
+        return Ok(false);
+    }
+
+    // The transcript itself has to fit, so long messages (i.e. code) are cut short
+    let older_turns: &[Message] = &agent.get_memory()[..kept_start];
+    let max_chars: usize = (token_limit as usize * 2) / older_turns.len();
+    let transcript: String = older_turns
+        .iter()
+        .map(|message| {
+            let content: String = message.content.chars().take(max_chars).collect();
+            format!("{}: {}", message.role.to_uppercase(), content)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let summary_msg: Message = extend_ai_function(print_memory_summary, &transcript);
+    let summary: String = request_llm(
+        vec![summary_msg],
+        agent.get_position(),
+        get_function_string!(print_memory_summary),
+    )
+    .await?;
+
+    let mut memory: Vec<Message> = vec![Message {
+        role: "system".to_string(),
+        content: format!("{} {}", SUMMARY_PREFIX, summary),
+    }];
+    memory.extend(agent.get_memory()[kept_start..].iter().cloned());
+    agent.memory = memory;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+    use crate::apis::providers::scripted::ScriptedProvider;
+    use crate::apis::providers::with_provider;
+    use std::sync::Arc;
+
+    fn agent_with_turns(position: &str, turns: usize, content_len: usize) -> BasicAgent {
+        let mut agent: BasicAgent =
+            BasicAgent::new("Tests the agent memory".to_string(), position.to_string());
+        for turn in 0..turns {
+            agent.add_memory(Message {
+                role: "user".to_string(),
+                content: format!("prompt {} {}", turn, "x".repeat(content_len)),
+            });
+            agent.add_memory(Message {
+                role: "assistant".to_string(),
+                content: format!("answer {} {}", turn, "y".repeat(content_len)),
+            });
+        }
+        agent
+    }
+
+    #[test]
+    fn tests_conversation_messages_sends_memory_window() {
+        let agent: BasicAgent = agent_with_turns("Backend Developer", 3, 0);
+        let prompt: Message = extend_ai_function(convert_user_input_to_goal, "fix the code");
+
+        let single: Vec<Message> = conversation_messages(&agent, prompt.clone(), 0);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].role, "system");
+
+        let windowed: Vec<Message> = conversation_messages(&agent, prompt, 2);
+        let contents: Vec<&str> = windowed.iter().map(|m| m.content.trim()).collect();
+        assert_eq!(windowed.len(), 6);
+        assert_eq!(windowed[0].role, "system");
+        assert_eq!(
+            contents[1..5],
+            ["prompt 1", "answer 1", "prompt 2", "answer 2"]
+        );
+        assert_eq!(windowed[5].role, "user");
+    }
+
+    #[test]
+    fn tests_model_context_uses_longest_prefix() {
+        assert_eq!(model_context_tokens("gpt-4o-mini"), 128_000);
+        assert_eq!(model_context_tokens("gpt-4-0613"), 8_192);
+        assert_eq!(model_context_tokens("llama3.1:8b"), 128_000);
+        assert_eq!(
+            model_context_tokens("unknown-model"),
+            DEFAULT_CONTEXT_TOKENS
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_compacts_memory_over_token_limit() {
+        let provider = Arc::new(ScriptedProvider::new().with_response(
+            "print_memory_summary",
+            "Wrote the backend, fixed a missing import",
+        ));
+        let mut agent: BasicAgent = agent_with_turns("Compacting Agent", 4, 2_000);
+        let prompt: Message = extend_ai_function(convert_user_input_to_goal, "fix the code");
+
+        // Window and token limit are passed in, so LLM_MEMORY_WINDOW, LLM_CONTEXT_TOKENS
+        // and LLM_MAX_TOKENS in the environment do not change the outcome
+        let messages: Vec<Message> = with_provider(
+            provider,
+            fit_conversation_within(&mut agent, prompt, 4, 3_000),
+        )
+        .await
+        .expect("Failed to compact memory");
+
+        assert!(estimate_messages_tokens(&messages) <= 3_000);
+        assert_eq!(agent.get_memory().len(), 3);
+        assert_eq!(
+            agent.get_memory()[0].content,
+            "SUMMARY OF EARLIER WORK: Wrote the backend, fixed a missing import"
+        );
+        assert!(agent.get_memory()[1].content.starts_with("prompt 3"));
+        assert!(messages[1].content.starts_with(SUMMARY_PREFIX));
+        assert_eq!(messages.last().unwrap().role, "user");
+    }
+}
@@ -1,2 +1,5 @@
 pub mod command_line;
 pub mod general;
+pub mod json_repair;
+pub mod memory;
+pub mod workspace;
@@ -0,0 +1,347 @@
+use crate::errors::GippityError;
+use crate::generators::openapi::OpenApiFormat;
+use crate::generators::sqlite::MIGRATION_FILE;
+
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Seeds used when a workspace does not have its own template or output project yet
+const DEFAULT_CODE_TEMPLATE: &str = include_str!("../../templates/code_template.rs");
+const DEFAULT_WEB_SERVER_CARGO: &str = include_str!("../../templates/web_server_cargo.toml");
+
+const WORKSPACE_CONFIG_FILE: &str = "gippity.json";
+
+// Workspace used by this process
+static CURRENT_WORKSPACE: OnceLock<Workspace> = OnceLock::new();
+
+#[cfg(test)]
+tokio::task_local! {
+    static WORKSPACE_OVERRIDE: &'static Workspace;
+}
+
+// Optional gippity.json at the workspace root. Relative paths are resolved from the root
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct WorkspaceFile {
+    template_dir: PathBuf,
+    output_dir: PathBuf,
+    schema_dir: PathBuf,
+    clients_dir: PathBuf,
+    frontend_dir: PathBuf,
+}
+
+impl Default for WorkspaceFile {
+    fn default() -> Self {
+        Self {
+            template_dir: PathBuf::from("web_template"),
+            output_dir: PathBuf::from("web_server"),
+            schema_dir: PathBuf::from("schemas"),
+            clients_dir: PathBuf::from("clients"),
+            frontend_dir: PathBuf::from("web_frontend"),
+        }
+    }
+}
+
+// Directories the agents read templates from and write generated projects to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub root: PathBuf,
+    // Project holding src/code_template.rs
+    pub template_dir: PathBuf,
+    // Cargo project the backend code is written into, built and run
+    pub output_dir: PathBuf,
+    pub schema_dir: PathBuf,
+    // Client libraries generated for the backend's API
+    pub clients_dir: PathBuf,
+    // Static frontend served next to the backend
+    pub frontend_dir: PathBuf,
+}
+
+impl Workspace {
+    // Resolves the directories from the root and its gippity.json, if any
+    pub fn from_root(root: impl Into<PathBuf>) -> Result<Self, GippityError> {
+        let root: PathBuf = root.into();
+        let config_path: PathBuf = root.join(WORKSPACE_CONFIG_FILE);
+
+        let config: WorkspaceFile = if config_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&config_path)?).map_err(|e| {
+                GippityError::Config(format!("Invalid {}: {}", config_path.display(), e))
+            })?
+        } else {
+            WorkspaceFile::default()
+        };
+
+        Ok(Self {
+            template_dir: root.join(config.template_dir),
+            output_dir: root.join(config.output_dir),
+            schema_dir: root.join(config.schema_dir),
+            clients_dir: root.join(config.clients_dir),
+            frontend_dir: root.join(config.frontend_dir),
+            root,
+        })
+    }
+
+    // Reads GIPPITY_WORKSPACE (defaults to "workspace"), then lets GIPPITY_TEMPLATE_DIR,
+    // GIPPITY_OUTPUT_DIR, GIPPITY_SCHEMA_DIR, GIPPITY_CLIENTS_DIR and GIPPITY_FRONTEND_DIR
+    // override single directories
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let root: String =
+            env::var("GIPPITY_WORKSPACE").unwrap_or_else(|_| "workspace".to_string());
+        Self::from_root_with_env(root)
+    }
+
+    // Resolves the directories from the given root, still honouring the directory overrides
+    pub fn from_root_with_env(root: impl Into<PathBuf>) -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let mut workspace: Self = Self::from_root(root)?;
+
+        if let Ok(template_dir) = env::var("GIPPITY_TEMPLATE_DIR") {
+            workspace.template_dir = workspace.root.join(template_dir);
+        }
+        if let Ok(output_dir) = env::var("GIPPITY_OUTPUT_DIR") {
+            workspace.output_dir = workspace.root.join(output_dir);
+        }
+        if let Ok(schema_dir) = env::var("GIPPITY_SCHEMA_DIR") {
+            workspace.schema_dir = workspace.root.join(schema_dir);
+        }
+        if let Ok(clients_dir) = env::var("GIPPITY_CLIENTS_DIR") {
+            workspace.clients_dir = workspace.root.join(clients_dir);
+        }
+        if let Ok(frontend_dir) = env::var("GIPPITY_FRONTEND_DIR") {
+            workspace.frontend_dir = workspace.root.join(frontend_dir);
+        }
+
+        Ok(workspace)
+    }
+
+    // Makes this the workspace used by the rest of the process. Must happen before first use
+    pub fn init(self) -> Result<&'static Workspace, GippityError> {
+        self.ensure()?;
+        CURRENT_WORKSPACE
+            .set(self)
+            .map_err(|_| GippityError::Config("Workspace is already initialised".to_string()))?;
+        Self::current()
+    }
+
+    // Workspace used by this process, read from the environment on first use
+    pub fn current() -> Result<&'static Workspace, GippityError> {
+        #[cfg(test)]
+        if let Ok(workspace) = WORKSPACE_OVERRIDE.try_with(|workspace| *workspace) {
+            return Ok(workspace);
+        }
+
+        if let Some(workspace) = CURRENT_WORKSPACE.get() {
+            return Ok(workspace);
+        }
+
+        let workspace: Self = Self::from_env()?;
+        workspace.ensure()?;
+        Ok(CURRENT_WORKSPACE.get_or_init(|| workspace))
+    }
+
+    // Runs the future with every file read and written in this workspace instead
+    // of the process one. The workspace is leaked, which is fine for a test
+    #[cfg(test)]
+    pub async fn with_workspace<F: std::future::Future>(
+        workspace: Workspace,
+        fut: F,
+    ) -> Result<F::Output, GippityError> {
+        workspace.ensure()?;
+        let workspace: &'static Workspace = Box::leak(Box::new(workspace));
+        Ok(WORKSPACE_OVERRIDE.scope(workspace, fut).await)
+    }
+
+    // Creates missing directories and seeds the template and output projects
+    pub fn ensure(&self) -> Result<(), GippityError> {
+        fs::create_dir_all(self.template_dir.join("src"))?;
+        fs::create_dir_all(self.output_dir.join("src"))?;
+        fs::create_dir_all(&self.schema_dir)?;
+
+        write_if_missing(&self.code_template_path(), DEFAULT_CODE_TEMPLATE)?;
+        write_if_missing(
+            &self.output_dir.join("Cargo.toml"),
+            DEFAULT_WEB_SERVER_CARGO,
+        )?;
+        write_if_missing(&self.exec_main_path(), DEFAULT_CODE_TEMPLATE)?;
+
+        Ok(())
+    }
+
+    pub fn code_template_path(&self) -> PathBuf {
+        self.template_dir.join("src").join("code_template.rs")
+    }
+
+    pub fn exec_main_path(&self) -> PathBuf {
+        self.output_dir.join("src").join("main.rs")
+    }
+
+    // Data-access module the backend declares as `mod db;`
+    pub fn database_module_path(&self) -> PathBuf {
+        self.output_dir.join("src").join("db.rs")
+    }
+
+    pub fn migration_path(&self) -> PathBuf {
+        self.output_dir.join("migrations").join(MIGRATION_FILE)
+    }
+
+    pub fn api_schema_path(&self) -> PathBuf {
+        self.schema_dir.join("api_schema.json")
+    }
+
+    // OpenAPI document next to the raw schema, e.g. schemas/openapi.yaml
+    pub fn openapi_path(&self, format: OpenApiFormat) -> PathBuf {
+        self.schema_dir
+            .join(format!("openapi.{}", format.file_extension()))
+    }
+
+    // Cargo project of the generated Rust client
+    pub fn rust_client_dir(&self) -> PathBuf {
+        self.clients_dir.join("rust")
+    }
+
+    // TypeScript module of the generated fetch client
+    pub fn typescript_client_path(&self) -> PathBuf {
+        self.clients_dir.join("typescript").join("api.ts")
+    }
+
+    pub fn frontend_index_path(&self) -> PathBuf {
+        self.frontend_dir.join("index.html")
+    }
+
+    // Directory holding one checkpoint directory per run
+    pub fn runs_dir(&self) -> PathBuf {
+        self.root.join("runs")
+    }
+
+    // Creates the directory of a new run, e.g. runs/run-1718000000
+    pub fn new_run_dir(&self) -> Result<PathBuf, GippityError> {
+        let started_secs: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        // create_dir fails on an existing directory, so runs started in the same second
+        // never share one even when they race
+        fs::create_dir_all(self.runs_dir())?;
+        let mut run_dir: PathBuf = self.runs_dir().join(format!("run-{}", started_secs));
+        let mut attempt: u32 = 1;
+        loop {
+            match fs::create_dir(&run_dir) {
+                Ok(()) => return Ok(run_dir),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    run_dir = self
+                        .runs_dir()
+                        .join(format!("run-{}-{}", started_secs, attempt));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+// Workspace in the temp dir for a single test, deleted with everything in it when dropped,
+// so failing tests clean up too. Also serves tests that just need a scratch directory
+#[cfg(test)]
+pub struct TempWorkspace {
+    workspace: Workspace,
+}
+
+#[cfg(test)]
+impl TempWorkspace {
+    // Empty workspace named after the test, e.g. auto_gippity_database_<pid>
+    pub fn new(name: &str) -> Self {
+        let root: PathBuf =
+            env::temp_dir().join(format!("auto_gippity_{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&root).ok();
+        let workspace: Workspace =
+            Workspace::from_root(root).expect("Unable to resolve the temp workspace");
+        Self { workspace }
+    }
+
+    // Runs the future with this workspace as the current one
+    pub async fn scope<F: std::future::Future>(&self, fut: F) -> F::Output {
+        Workspace::with_workspace(self.workspace.clone(), fut)
+            .await
+            .expect("Unable to create the temp workspace")
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempWorkspace {
+    type Target = Workspace;
+
+    fn deref(&self) -> &Workspace {
+        &self.workspace
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempWorkspace {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.workspace.root).ok();
+    }
+}
+
+fn write_if_missing(path: &Path, contents: &str) -> Result<(), GippityError> {
+    if !path.exists() {
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_creates_and_seeds_workspace() {
+        let workspace: TempWorkspace = TempWorkspace::new("workspace_seed");
+
+        workspace.ensure().unwrap();
+
+        assert_eq!(workspace.output_dir, workspace.root.join("web_server"));
+        assert!(workspace.code_template_path().exists());
+        assert!(workspace.output_dir.join("Cargo.toml").exists());
+        assert!(workspace.schema_dir.is_dir());
+    }
+
+    #[test]
+    fn tests_gives_every_run_its_own_dir() {
+        let workspace: TempWorkspace = TempWorkspace::new("workspace_runs");
+
+        let run_dirs: Vec<PathBuf> = (0..3).map(|_| workspace.new_run_dir().unwrap()).collect();
+
+        assert!(run_dirs.iter().all(|run_dir| run_dir.is_dir()));
+        assert_ne!(run_dirs[0], run_dirs[1]);
+        assert_ne!(run_dirs[1], run_dirs[2]);
+        assert_ne!(run_dirs[0], run_dirs[2]);
+    }
+
+    #[test]
+    fn tests_reads_workspace_config_file() {
+        let temp_dir: TempWorkspace = TempWorkspace::new("workspace_config");
+        let root: PathBuf = temp_dir.root.clone();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join(WORKSPACE_CONFIG_FILE),
+            r#"{"output_dir": "generated/backend", "schema_dir": "/tmp/schemas"}"#,
+        )
+        .unwrap();
+
+        let workspace: Workspace = Workspace::from_root(&root).unwrap();
+
+        assert_eq!(workspace.template_dir, root.join("web_template"));
+        assert_eq!(workspace.output_dir, root.join("generated/backend"));
+        assert_eq!(workspace.schema_dir, PathBuf::from("/tmp/schemas"));
+    }
+}
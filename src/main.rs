@@ -8,12 +8,21 @@ macro_rules! get_function_string {
 #[macro_use]
 mod ai_functions;
 mod apis;
+mod cli;
+mod errors;
+mod generators;
 mod helpers;
 mod models;
+mod runner;
 
-use helpers::command_line::get_user_response;
+use clap::Parser;
+use cli::Cli;
+use std::process;
 
-fn main() {
-    let user_req: String = get_user_response("What are we building today?");
-    dbg!("{}", user_req);
+#[tokio::main]
+async fn main() {
+    // Errors are reported by the command itself, the exit status lets scripts notice
+    if Cli::parse().run().await.is_err() {
+        process::exit(1);
+    }
 }
@@ -1,7 +1,8 @@
 use crate::models::agent_basic::basic_traits::BasicTraits;
 use crate::models::general::llm::Message;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum AgentState {
     Discovery,
     Working,
@@ -9,7 +10,7 @@ pub enum AgentState {
     Finished,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BasicAgent {
     pub objective: String,
     pub position: String,
@@ -46,4 +47,8 @@ impl BasicTraits for BasicAgent {
     fn get_memory(&self) -> &Vec<Message> {
         &self.memory
     }
+
+    fn add_memory(&mut self, message: Message) {
+        self.memory.push(message);
+    }
 }
@@ -0,0 +1,448 @@
+use crate::ai_functions::aifunc_backend::{
+    print_backend_webserver_code, print_fixed_code, print_improved_webserver_code,
+    print_rest_api_endpoints,
+};
+use crate::errors::GippityError;
+use crate::generators::openapi::{openapi_document, render_openapi, OpenApiFormat};
+use crate::generators::rust_client::rust_client;
+use crate::generators::sqlite::data_access_module;
+use crate::generators::typescript_client::typescript_client;
+use crate::helpers::command_line::{confirm_safe_code, PrintCommand};
+use crate::helpers::general::{
+    ai_task_request, ai_task_request_decoded, read_code_template_contents, read_exec_main_contents,
+    save_api_endpoints, save_backend_code, save_openapi_document, save_rust_client,
+    save_typescript_client,
+};
+use crate::helpers::workspace::Workspace;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::agents::agent_traits::{FactSheet, RouteObject, SpecialFunctions};
+use crate::runner::build::{BuildReport, BuildRunner};
+use crate::runner::contract::{ContractReport, ContractTester, RouteResult};
+use crate::runner::sandbox::Sandbox;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Maximum number of times the agent tries to fix compiler errors before giving up
+const MAX_BUG_FIX_ATTEMPTS: u8 = 3;
+
+// Backend Developer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentBackendDeveloper {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    // Fix prompts sent since the backend last built
+    bug_count: u8,
+}
+
+impl AgentBackendDeveloper {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent::new(
+            "Develops backend code for webserver and json database".to_string(),
+            "Backend Developer".to_string(),
+        );
+
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+        }
+    }
+
+    // Writes the first version of the backend based on the code template
+    async fn call_initial_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let code_template_str: String = read_code_template_contents()?;
+
+        let msg_context: String = format!(
+            "CODE TEMPLATE: {} \n PROJECT_DESCRIPTION: {} \n {}",
+            code_template_str,
+            factsheet.project_description,
+            database_context(factsheet)?
+        );
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_backend_webserver_code),
+            print_backend_webserver_code,
+        )
+        .await?;
+
+        save_backend_code(&ai_response)?;
+        factsheet.backend_code = Some(ai_response);
+        Ok(())
+    }
+
+    // Improves the current backend code against the project description and database
+    async fn call_improved_backend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "CODE TEMPLATE: {:?} \n PROJECT_DESCRIPTION: {} \n {}",
+            factsheet.backend_code,
+            factsheet.project_description,
+            database_context(factsheet)?
+        );
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_improved_webserver_code),
+            print_improved_webserver_code,
+        )
+        .await?;
+
+        save_backend_code(&ai_response)?;
+        factsheet.backend_code = Some(ai_response);
+        Ok(())
+    }
+
+    // Fixes the backend code using the errors found while building it
+    async fn call_fix_code_bugs(&mut self, factsheet: &mut FactSheet) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "BROKEN_CODE: {:?} \n ERROR_BUGS: {} \n
+            THIS FUNCTION ONLY OUTPUTS CODE. JUST OUTPUT THE CODE.",
+            factsheet.backend_code,
+            self.bug_errors.as_deref().unwrap_or_default()
+        );
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_fixed_code),
+            print_fixed_code,
+        )
+        .await?;
+
+        save_backend_code(&ai_response)?;
+        factsheet.backend_code = Some(ai_response);
+        self.bug_count += 1;
+        Ok(())
+    }
+
+    // Extracts the REST API endpoints from the saved backend code
+    async fn call_extract_rest_api_endpoints(&mut self) -> Result<Vec<RouteObject>, GippityError> {
+        let backend_code: String = read_exec_main_contents()?;
+
+        let msg_context: String = format!("CODE_INPUT: {}", backend_code);
+
+        ai_task_request_decoded::<Vec<RouteObject>>(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_rest_api_endpoints),
+            print_rest_api_endpoints,
+        )
+        .await
+    }
+
+    // Writes the backend code into the project, builds it and records the compiler errors
+    async fn build_backend(&mut self, factsheet: &FactSheet) -> Result<bool, GippityError> {
+        PrintCommand::UnitTest.print_agent_message(
+            &self.attributes.position,
+            "Backend Code Unit Testing: building project...",
+        );
+
+        if let Some(isolation_error) = &Sandbox::current()?.isolation_error {
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, isolation_error);
+        }
+
+        let backend_code: &str = factsheet
+            .backend_code
+            .as_deref()
+            .ok_or_else(|| GippityError::Agent("No backend code to build".to_string()))?;
+
+        let build_runner: BuildRunner =
+            BuildRunner::from_env(Workspace::current()?.output_dir.clone())?;
+        let build_report: BuildReport = build_runner.build_code(backend_code).await?;
+
+        if let Some(violation) = build_report.violation {
+            let violation_msg: String = format!("Sandbox: the backend build was {}", violation);
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, &violation_msg);
+        }
+
+        if build_report.success {
+            self.bug_count = 0;
+            self.bug_errors = None;
+            PrintCommand::UnitTest.print_agent_message(
+                &self.attributes.position,
+                "Backend Code Unit Testing: Test server build successful...",
+            );
+            return Ok(true);
+        }
+
+        let build_msg: String = format!(
+            "Backend Code Unit Testing: build failed with {} errors",
+            build_report.errors().len()
+        );
+        PrintCommand::Issue.print_agent_message(&self.attributes.position, &build_msg);
+
+        self.bug_errors = Some(build_report.error_summary());
+        Ok(false)
+    }
+
+    // Generates the Rust client for the endpoints and compiles it. A client that cannot be
+    // saved or built, or does not compile because the schema contradicts itself, is reported
+    // but not fatal
+    async fn generate_rust_client(&self, api_endpoints: &[RouteObject]) {
+        let build_report: BuildReport = match build_rust_client(api_endpoints).await {
+            Ok(build_report) => build_report,
+            Err(error) => {
+                let error_msg: String =
+                    format!("Unable to build the generated Rust client: {}", error);
+                PrintCommand::Issue.print_agent_message(&self.attributes.position, &error_msg);
+                return;
+            }
+        };
+
+        if build_report.success {
+            PrintCommand::UnitTest.print_agent_message(
+                &self.attributes.position,
+                "Backend Code Unit Testing: generated Rust client compiles...",
+            );
+        } else {
+            let client_msg: String = format!(
+                "Generated Rust client does not compile, the API schema is inconsistent: {}",
+                build_report.error_summary()
+            );
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, &client_msg);
+        }
+    }
+
+    // Runs the backend and contract tests every route, reporting the verdict per route
+    async fn test_endpoints(&self, api_endpoints: &[RouteObject]) -> Result<bool, GippityError> {
+        PrintCommand::UnitTest.print_agent_message(
+            &self.attributes.position,
+            "Backend Code Unit Testing: Starting web server...",
+        );
+
+        let output_dir: PathBuf = Workspace::current()?.output_dir.clone();
+        let executable: PathBuf = BuildRunner::from_env(output_dir.clone())?
+            .executable()
+            .await?;
+        let contract_report: ContractReport = ContractTester::from_env(output_dir)?
+            .test_routes(&executable, api_endpoints)
+            .await?;
+        let route_results: &[RouteResult] = &contract_report.route_results;
+
+        if let Some(violation) = contract_report.violation {
+            let violation_msg: String = format!("Sandbox: the web server was {}", violation);
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, &violation_msg);
+        }
+
+        for route_result in route_results {
+            let route_msg: String = route_result.to_string();
+            if route_result.passed() {
+                PrintCommand::UnitTest.print_agent_message(&self.attributes.position, &route_msg);
+            } else {
+                PrintCommand::Issue.print_agent_message(&self.attributes.position, &route_msg);
+            }
+        }
+
+        let passed_count: usize = route_results.iter().filter(|r| r.passed()).count();
+        let summary_msg: String = format!(
+            "Backend Code Unit Testing: {} of {} routes passed",
+            passed_count,
+            route_results.len()
+        );
+        PrintCommand::UnitTest.print_agent_message(&self.attributes.position, &summary_msg);
+
+        Ok(passed_count == route_results.len())
+    }
+}
+
+// Saves the Rust client for the endpoints and builds it
+async fn build_rust_client(api_endpoints: &[RouteObject]) -> Result<BuildReport, GippityError> {
+    let client_dir: PathBuf = save_rust_client(&rust_client(api_endpoints))?;
+    BuildRunner::from_env(client_dir)?.build().await
+}
+
+// Schema and data-access module the backend stores its data through, when one was designed
+fn database_context(factsheet: &FactSheet) -> Result<String, GippityError> {
+    match &factsheet.database_schema {
+        Some(database_schema) => Ok(format!(
+            "DATABASE_SCHEMA: {} \n DATA_ACCESS_MODULE: {} \n",
+            serde_json::to_string_pretty(database_schema)?,
+            data_access_module(database_schema)
+        )),
+        None => Ok(String::new()),
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentBackendDeveloper {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn checkpoint(&self) -> Result<serde_json::Value, GippityError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn restore(&mut self, checkpoint: serde_json::Value) -> Result<(), GippityError> {
+        *self = serde_json::from_value(checkpoint)?;
+        Ok(())
+    }
+
+    async fn execute_step(&mut self, factsheet: &mut FactSheet) -> Result<(), GippityError> {
+        match &self.attributes.state {
+            AgentState::Discovery => {
+                self.call_initial_backend_code(factsheet).await?;
+                self.attributes.state = AgentState::Working;
+            }
+
+            AgentState::Working => {
+                if self.bug_errors.is_none() {
+                    self.call_improved_backend_code(factsheet).await?;
+                } else {
+                    self.call_fix_code_bugs(factsheet).await?;
+                }
+                self.attributes.state = AgentState::UnitTesting;
+            }
+
+            AgentState::UnitTesting => {
+                // Guard: never run AI generated code without the user's consent
+                PrintCommand::UnitTest.print_agent_message(
+                    &self.attributes.position,
+                    "Backend Code Unit Testing: Requesting user input",
+                );
+
+                if !confirm_safe_code() {
+                    return Err(GippityError::Agent(
+                        "User refused to run the AI generated backend code".to_string(),
+                    ));
+                }
+
+                if !self.build_backend(factsheet).await? {
+                    if self.bug_count >= MAX_BUG_FIX_ATTEMPTS {
+                        PrintCommand::Issue.print_agent_message(
+                            &self.attributes.position,
+                            "Backend Code Unit Testing: Too many bugs found in code",
+                        );
+                        return Err(GippityError::Agent(format!(
+                            "Backend still fails to build after {} attempts to fix it",
+                            self.bug_count
+                        )));
+                    }
+
+                    self.attributes.state = AgentState::Working;
+                    return Ok(());
+                }
+
+                // Extracts and records the API endpoints
+                let api_endpoints: Vec<RouteObject> =
+                    self.call_extract_rest_api_endpoints().await?;
+                let api_endpoints_str: String = serde_json::to_string_pretty(&api_endpoints)?;
+
+                factsheet.api_endpoint_schema = Some(api_endpoints.clone());
+                save_api_endpoints(&api_endpoints_str)?;
+
+                let openapi_format: OpenApiFormat = OpenApiFormat::from_env()?;
+                let openapi_str: String = render_openapi(
+                    &openapi_document(&factsheet.project_description, &api_endpoints),
+                    openapi_format,
+                )?;
+                save_openapi_document(&openapi_str, openapi_format)?;
+                self.generate_rust_client(&api_endpoints).await;
+                save_typescript_client(&typescript_client(&api_endpoints))?;
+
+                if self.test_endpoints(&api_endpoints).await? {
+                    PrintCommand::UnitTest.print_agent_message(
+                        &self.attributes.position,
+                        "Backend testing complete...",
+                    );
+                }
+
+                self.attributes.state = AgentState::Finished;
+            }
+
+            AgentState::Finished => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::providers::scripted::ScriptedProvider;
+    use crate::apis::providers::with_provider;
+    use crate::helpers::command_line::set_auto_confirm;
+    use crate::helpers::workspace::TempWorkspace;
+    use std::fs;
+    use std::sync::Arc;
+
+    const BROKEN_CODE: &str = "fn main() {\n    println!(\"{}\", x);\n}\n";
+
+    #[tokio::test]
+    async fn tests_backend_developer_gives_up_after_failed_builds() {
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response("print_backend_webserver_code", "fn main() {}\n")
+                .with_response("print_improved_webserver_code", BROKEN_CODE)
+                .with_response("print_fixed_code", BROKEN_CODE),
+        );
+
+        let mut factsheet: FactSheet = FactSheet {
+            project_description: "build a website that keeps a todo list".to_string(),
+            project_scope: None,
+            external_urls: None,
+            backend_code: None,
+            api_endpoint_schema: None,
+            frontend_code: None,
+            database_schema: None,
+        };
+
+        let workspace: TempWorkspace = TempWorkspace::new("backend");
+        workspace.ensure().unwrap();
+        // A project without dependencies, so the broken code fails to build within seconds
+        fs::write(
+            workspace.output_dir.join("Cargo.toml"),
+            "[package]\nname = \"web_server\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        set_auto_confirm(true);
+
+        let mut agent: AgentBackendDeveloper = AgentBackendDeveloper::new();
+        let run = async {
+            // Initial code, then the improved code, then the first failing build
+            for _ in 0..3 {
+                with_provider(provider.clone(), agent.execute_step(&mut factsheet))
+                    .await
+                    .expect("Unable to execute Backend Developer Agent");
+            }
+            assert_eq!(agent.attributes.state, AgentState::Working);
+            assert_eq!(agent.bug_count, 0);
+            assert!(agent.bug_errors.as_deref().unwrap().contains("E0425"));
+            assert_eq!(
+                fs::read_to_string(workspace.exec_main_path()).unwrap(),
+                BROKEN_CODE
+            );
+
+            // Every fix keeps failing until the agent runs out of attempts
+            loop {
+                let step: Result<(), GippityError> =
+                    with_provider(provider.clone(), agent.execute_step(&mut factsheet)).await;
+                if let Err(error) = step {
+                    break error;
+                }
+            }
+        };
+        let error: GippityError = workspace.scope(run).await;
+
+        assert!(matches!(error, GippityError::Agent(_)));
+        assert_eq!(
+            provider.call_count("print_fixed_code"),
+            MAX_BUG_FIX_ATTEMPTS as usize
+        );
+        assert_eq!(agent.bug_count, MAX_BUG_FIX_ATTEMPTS);
+        assert_eq!(factsheet.backend_code.as_deref(), Some(BROKEN_CODE));
+        assert!(factsheet.api_endpoint_schema.is_none());
+    }
+}
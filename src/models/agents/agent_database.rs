@@ -0,0 +1,301 @@
+use crate::ai_functions::aifunc_database::{print_database_schema, print_fixed_database_schema};
+use crate::errors::GippityError;
+use crate::generators::sqlite::{data_access_module, schema_issues, sqlite_migration};
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{ai_task_request_decoded, save_database_code};
+use crate::helpers::workspace::Workspace;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::agents::agent_traits::{DatabaseSchema, FactSheet, SpecialFunctions};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+// Maximum number of times the agent tries to fix the designed schema before giving up
+const MAX_FIX_ATTEMPTS: u8 = 2;
+
+// Database Designer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentDatabaseDesigner {
+    attributes: BasicAgent,
+    schema_issues: Option<String>,
+    // Fix prompts sent since the schema last passed its checks
+    fix_count: u8,
+}
+
+impl AgentDatabaseDesigner {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent::new(
+            "Designs the database schema and data access code for websites storing data"
+                .to_string(),
+            "Database Designer".to_string(),
+        );
+
+        Self {
+            attributes,
+            schema_issues: None,
+            fix_count: 0,
+        }
+    }
+
+    // Designs the tables from the project description and scope
+    async fn call_database_schema(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "PROJECT_DESCRIPTION: {} \n PROJECT_SCOPE: {:?} \n",
+            factsheet.project_description, factsheet.project_scope
+        );
+
+        let ai_response: DatabaseSchema = ai_task_request_decoded::<DatabaseSchema>(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_database_schema),
+            print_database_schema,
+        )
+        .await?;
+
+        factsheet.database_schema = Some(ai_response);
+        Ok(())
+    }
+
+    // Redesigns the tables using the issues found in the schema
+    async fn call_fix_database_schema(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "BROKEN_SCHEMA: {} \n PROJECT_DESCRIPTION: {} \n ISSUES: {} \n",
+            serde_json::to_string_pretty(&factsheet.database_schema)?,
+            factsheet.project_description,
+            self.schema_issues.as_deref().unwrap_or_default()
+        );
+
+        let ai_response: DatabaseSchema = ai_task_request_decoded::<DatabaseSchema>(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_fixed_database_schema),
+            print_fixed_database_schema,
+        )
+        .await?;
+
+        factsheet.database_schema = Some(ai_response);
+        self.fix_count += 1;
+        Ok(())
+    }
+
+    // Checks the schema can be turned into a migration, recording the issues when not
+    fn check_schema(&mut self, database_schema: &DatabaseSchema) -> bool {
+        let issues: Vec<String> = schema_issues(database_schema);
+        if issues.is_empty() {
+            self.fix_count = 0;
+            self.schema_issues = None;
+            return true;
+        }
+
+        for issue in &issues {
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, issue);
+        }
+
+        self.schema_issues = Some(issues.join("\n"));
+        false
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentDatabaseDesigner {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn checkpoint(&self) -> Result<serde_json::Value, GippityError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn restore(&mut self, checkpoint: serde_json::Value) -> Result<(), GippityError> {
+        *self = serde_json::from_value(checkpoint)?;
+        Ok(())
+    }
+
+    async fn execute_step(&mut self, factsheet: &mut FactSheet) -> Result<(), GippityError> {
+        match &self.attributes.state {
+            AgentState::Discovery => {
+                let is_crud_required: bool = factsheet
+                    .project_scope
+                    .map(|scope| scope.is_crud_required)
+                    .unwrap_or_default();
+
+                if !is_crud_required {
+                    PrintCommand::UnitTest.print_agent_message(
+                        &self.attributes.position,
+                        "No CRUD required, the backend keeps its data in memory",
+                    );
+                    self.attributes.state = AgentState::Finished;
+                    return Ok(());
+                }
+
+                self.call_database_schema(factsheet).await?;
+                self.attributes.state = AgentState::UnitTesting;
+            }
+
+            AgentState::Working => {
+                self.call_fix_database_schema(factsheet).await?;
+                self.attributes.state = AgentState::UnitTesting;
+            }
+
+            AgentState::UnitTesting => {
+                let database_schema: DatabaseSchema =
+                    factsheet.database_schema.clone().ok_or_else(|| {
+                        GippityError::Agent("No database schema to check".to_string())
+                    })?;
+
+                if !self.check_schema(&database_schema) {
+                    if self.fix_count >= MAX_FIX_ATTEMPTS {
+                        return Err(GippityError::Agent(format!(
+                            "Database schema still has issues after {} attempts to fix it",
+                            self.fix_count
+                        )));
+                    }
+
+                    self.attributes.state = AgentState::Working;
+                    return Ok(());
+                }
+
+                save_database_code(
+                    &sqlite_migration(&database_schema),
+                    &data_access_module(&database_schema),
+                )?;
+
+                let saved_msg: String = format!(
+                    "Database Unit Testing: {} tables, migration saved to {}",
+                    database_schema.tables.len(),
+                    Workspace::current()?.migration_path().display()
+                );
+                PrintCommand::UnitTest.print_agent_message(&self.attributes.position, &saved_msg);
+                self.attributes.state = AgentState::Finished;
+            }
+
+            AgentState::Finished => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::providers::scripted::ScriptedProvider;
+    use crate::apis::providers::with_provider;
+    use crate::helpers::workspace::TempWorkspace;
+    use crate::models::agents::agent_traits::ProjectScope;
+    use std::fs;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tests_database_designer_fixes_schema_when_crud_required() {
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response(
+                    "print_database_schema",
+                    r#"{"tables": [{"name": "tasks", "columns": [{"name": "id", "type": "integer", "primary_key": true}, {"name": "user_id", "type": "integer", "references": "users"}]}]}"#,
+                )
+                .with_response(
+                    "print_fixed_database_schema",
+                    r#"{"tables": [{"name": "users", "columns": [{"name": "id", "type": "integer", "primary_key": true}]}, {"name": "tasks", "columns": [{"name": "id", "type": "integer", "primary_key": true}, {"name": "user_id", "type": "integer", "references": "users"}]}]}"#,
+                ),
+        );
+
+        let mut factsheet: FactSheet = FactSheet {
+            project_description: "build a website where users keep a todo list".to_string(),
+            project_scope: Some(ProjectScope {
+                is_crud_required: true,
+                is_user_login_and_logout: true,
+                is_external_urls_required: false,
+            }),
+            external_urls: None,
+            backend_code: None,
+            api_endpoint_schema: None,
+            frontend_code: None,
+            database_schema: None,
+        };
+
+        let workspace: TempWorkspace = TempWorkspace::new("database");
+
+        let mut agent: AgentDatabaseDesigner = AgentDatabaseDesigner::new();
+        workspace
+            .scope(async {
+                while agent.attributes.state != AgentState::Finished {
+                    with_provider(provider.clone(), agent.execute_step(&mut factsheet))
+                        .await
+                        .expect("Unable to execute Database Designer Agent");
+                }
+            })
+            .await;
+
+        let database_schema: &DatabaseSchema = factsheet.database_schema.as_ref().unwrap();
+        assert_eq!(database_schema.tables.len(), 2);
+        assert!(workspace.database_module_path().exists());
+        assert!(workspace.migration_path().exists());
+        let cargo_toml: String =
+            fs::read_to_string(workspace.output_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("[dependencies]\nrusqlite = "));
+        assert_eq!(provider.call_count("print_fixed_database_schema"), 1);
+        assert_eq!(agent.fix_count, 0);
+
+        // Without CRUD the agent is done before asking for anything
+        let mut no_crud: AgentDatabaseDesigner = AgentDatabaseDesigner::new();
+        factsheet.project_scope.as_mut().unwrap().is_crud_required = false;
+        factsheet.database_schema = None;
+        no_crud.execute_step(&mut factsheet).await.unwrap();
+        assert_eq!(no_crud.attributes.state, AgentState::Finished);
+        assert!(factsheet.database_schema.is_none());
+    }
+
+    #[tokio::test]
+    async fn tests_database_designer_gives_up_after_max_fixes() {
+        let broken_schema: &str = r#"{"tables": [{"name": "tasks", "columns": [{"name": "user_id", "type": "integer", "references": "users"}]}]}"#;
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response("print_database_schema", broken_schema)
+                .with_response("print_fixed_database_schema", broken_schema),
+        );
+
+        let mut factsheet: FactSheet = FactSheet {
+            project_description: "build a website where users keep a todo list".to_string(),
+            project_scope: Some(ProjectScope {
+                is_crud_required: true,
+                is_user_login_and_logout: false,
+                is_external_urls_required: false,
+            }),
+            external_urls: None,
+            backend_code: None,
+            api_endpoint_schema: None,
+            frontend_code: None,
+            database_schema: None,
+        };
+
+        let workspace: TempWorkspace = TempWorkspace::new("database_broken");
+
+        let mut agent: AgentDatabaseDesigner = AgentDatabaseDesigner::new();
+        let error: GippityError = workspace
+            .scope(async {
+                loop {
+                    let step: Result<(), GippityError> =
+                        with_provider(provider.clone(), agent.execute_step(&mut factsheet)).await;
+                    if let Err(error) = step {
+                        break error;
+                    }
+                }
+            })
+            .await;
+
+        assert!(matches!(error, GippityError::Agent(_)));
+        assert_eq!(
+            provider.call_count("print_fixed_database_schema"),
+            MAX_FIX_ATTEMPTS as usize
+        );
+        assert!(!workspace.migration_path().exists());
+    }
+}
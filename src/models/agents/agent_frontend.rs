@@ -0,0 +1,385 @@
+use crate::ai_functions::aifunc_frontend::{
+    print_fixed_frontend_code, print_frontend_code, print_improved_frontend_code,
+};
+use crate::errors::GippityError;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::{ai_task_request, save_frontend_code};
+use crate::helpers::workspace::Workspace;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::agents::agent_traits::{FactSheet, PathSegment, RouteObject, SpecialFunctions};
+use crate::runner::contract::ContractTester;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+// Maximum number of times the agent tries to fix the issues found in the page before giving up
+const MAX_FIX_ATTEMPTS: u8 = 2;
+
+// Frontend Developer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentFrontendDeveloper {
+    attributes: BasicAgent,
+    frontend_issues: Option<String>,
+    // Fix prompts sent since the page last passed its checks
+    fix_count: u8,
+}
+
+impl AgentFrontendDeveloper {
+    pub fn new() -> Self {
+        let attributes: BasicAgent = BasicAgent::new(
+            "Develops a static frontend for the website that calls the backend API".to_string(),
+            "Frontend Developer".to_string(),
+        );
+
+        Self {
+            attributes,
+            frontend_issues: None,
+            fix_count: 0,
+        }
+    }
+
+    // Project description, backend address and endpoints every prompt works from
+    fn api_context(factsheet: &FactSheet) -> Result<String, GippityError> {
+        let api_endpoints: &Vec<RouteObject> =
+            factsheet.api_endpoint_schema.as_ref().ok_or_else(|| {
+                GippityError::Agent(
+                    "No API endpoint schema to build a frontend against".to_string(),
+                )
+            })?;
+        let api_base_url: String =
+            ContractTester::from_env(Workspace::current()?.output_dir.clone())?.base_url();
+
+        Ok(format!(
+            "PROJECT_DESCRIPTION: {} \n API_BASE_URL: {} \n API_ENDPOINTS: {} \n",
+            factsheet.project_description,
+            api_base_url,
+            serde_json::to_string_pretty(api_endpoints)?
+        ))
+    }
+
+    // Writes the first version of the page from the description and the API
+    async fn call_initial_frontend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = Self::api_context(factsheet)?;
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_frontend_code),
+            print_frontend_code,
+        )
+        .await?;
+
+        self.store_frontend_code(factsheet, &ai_response)
+    }
+
+    // Reviews the page against the description and the API
+    async fn call_improved_frontend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "FRONTEND_CODE: {} \n {}",
+            factsheet.frontend_code.as_deref().unwrap_or_default(),
+            Self::api_context(factsheet)?
+        );
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_improved_frontend_code),
+            print_improved_frontend_code,
+        )
+        .await?;
+
+        self.store_frontend_code(factsheet, &ai_response)
+    }
+
+    // Fixes the issues found while checking the page
+    async fn call_fix_frontend_issues(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), GippityError> {
+        let msg_context: String = format!(
+            "BROKEN_CODE: {} \n {} ISSUES: {} \n
+            THIS FUNCTION ONLY OUTPUTS CODE. JUST OUTPUT THE CODE.",
+            factsheet.frontend_code.as_deref().unwrap_or_default(),
+            Self::api_context(factsheet)?,
+            self.frontend_issues.as_deref().unwrap_or_default()
+        );
+
+        let ai_response: String = ai_task_request(
+            msg_context,
+            &mut self.attributes,
+            get_function_string!(print_fixed_frontend_code),
+            print_fixed_frontend_code,
+        )
+        .await?;
+
+        self.fix_count += 1;
+        self.store_frontend_code(factsheet, &ai_response)
+    }
+
+    fn store_frontend_code(
+        &self,
+        factsheet: &mut FactSheet,
+        ai_response: &str,
+    ) -> Result<(), GippityError> {
+        let frontend_code: &str = extract_html(ai_response);
+        save_frontend_code(frontend_code)?;
+        factsheet.frontend_code = Some(frontend_code.to_string());
+        Ok(())
+    }
+
+    // Checks the page is a complete document with a script using every endpoint
+    fn check_frontend(&mut self, factsheet: &FactSheet) -> bool {
+        let frontend_code: &str = factsheet.frontend_code.as_deref().unwrap_or_default();
+        let api_endpoints: &[RouteObject] =
+            factsheet.api_endpoint_schema.as_deref().unwrap_or_default();
+
+        let issues: Vec<String> = frontend_issues(frontend_code, api_endpoints);
+        if issues.is_empty() {
+            self.fix_count = 0;
+            self.frontend_issues = None;
+            PrintCommand::UnitTest.print_agent_message(
+                &self.attributes.position,
+                "Frontend Code Unit Testing: every endpoint is used by the page...",
+            );
+            return true;
+        }
+
+        for issue in &issues {
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, issue);
+        }
+
+        self.frontend_issues = Some(issues.join("\n"));
+        false
+    }
+}
+
+// The HTML document out of the model's answer, without markdown fences or chatter around it
+pub fn extract_html(raw: &str) -> &str {
+    let trimmed: &str = raw.trim();
+    let lowercase: String = trimmed.to_ascii_lowercase();
+
+    let start: Option<usize> = lowercase
+        .find("<!doctype")
+        .or_else(|| lowercase.find("<html"));
+    let end: Option<usize> = lowercase.rfind("</html>").map(|end| end + "</html>".len());
+
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..end],
+        _ => trimmed,
+    }
+}
+
+// Problems a page cannot have: no document, no script, or endpoints it never calls.
+// An endpoint counts as used when the static part of its route before the first parameter
+// appears, e.g. "/item/" for /item/{id}
+pub fn frontend_issues(frontend_code: &str, api_endpoints: &[RouteObject]) -> Vec<String> {
+    let mut issues: Vec<String> = vec![];
+    let lowercase: String = frontend_code.to_ascii_lowercase();
+
+    if !lowercase.contains("<html") {
+        issues.push("The page is not a complete HTML document".to_string());
+    }
+    if !lowercase.contains("<script") {
+        issues.push("The page has no script calling the API".to_string());
+    }
+
+    for route_object in api_endpoints {
+        let segments: &[PathSegment] = route_object.route.segments();
+        let mut static_prefix: String = segments
+            .iter()
+            .map_while(|segment| match segment {
+                PathSegment::Static(text) => Some(format!("/{}", text)),
+                PathSegment::Param(_) => None,
+            })
+            .collect();
+        if route_object.route.is_dynamic() {
+            static_prefix.push('/');
+        }
+
+        if !static_prefix.is_empty() && !frontend_code.contains(&static_prefix) {
+            issues.push(format!(
+                "The page never calls {} {}",
+                route_object.method.to_string().to_uppercase(),
+                route_object.route
+            ));
+        }
+    }
+
+    issues
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentFrontendDeveloper {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn checkpoint(&self) -> Result<serde_json::Value, GippityError> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn restore(&mut self, checkpoint: serde_json::Value) -> Result<(), GippityError> {
+        *self = serde_json::from_value(checkpoint)?;
+        Ok(())
+    }
+
+    async fn execute_step(&mut self, factsheet: &mut FactSheet) -> Result<(), GippityError> {
+        match &self.attributes.state {
+            AgentState::Discovery => {
+                self.call_initial_frontend_code(factsheet).await?;
+                self.attributes.state = AgentState::Working;
+            }
+
+            AgentState::Working => {
+                if self.frontend_issues.is_none() {
+                    self.call_improved_frontend_code(factsheet).await?;
+                } else {
+                    self.call_fix_frontend_issues(factsheet).await?;
+                }
+                self.attributes.state = AgentState::UnitTesting;
+            }
+
+            AgentState::UnitTesting => {
+                PrintCommand::UnitTest.print_agent_message(
+                    &self.attributes.position,
+                    "Frontend Code Unit Testing: checking the page against the API...",
+                );
+
+                // A page missing a few endpoints is still worth keeping, so running out
+                // of attempts finishes the agent instead of failing the run
+                if !self.check_frontend(factsheet) && self.fix_count < MAX_FIX_ATTEMPTS {
+                    self.attributes.state = AgentState::Working;
+                    return Ok(());
+                }
+
+                let saved_msg: String = format!(
+                    "Frontend saved to {}",
+                    Workspace::current()?.frontend_index_path().display()
+                );
+                PrintCommand::UnitTest.print_agent_message(&self.attributes.position, &saved_msg);
+                self.attributes.state = AgentState::Finished;
+            }
+
+            AgentState::Finished => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::providers::scripted::{ScriptedProvider, ANY_FUNCTION};
+    use crate::apis::providers::with_provider;
+    use crate::helpers::workspace::TempWorkspace;
+    use std::fs;
+    use std::sync::Arc;
+
+    // Page that never calls GET /item/{id}
+    const PARTIAL_PAGE: &str = concat!(
+        "```html\n<!DOCTYPE html>\n<html><body><script>\n",
+        "fetch(API_BASE_URL + \"/item\", { method: \"POST\" });\n",
+        "</script></body></html>\n```"
+    );
+
+    fn test_factsheet() -> FactSheet {
+        FactSheet {
+            project_description: "build a website that keeps a todo list".to_string(),
+            project_scope: None,
+            external_urls: None,
+            backend_code: None,
+            api_endpoint_schema: Some(
+                serde_json::from_str(
+                    r#"[
+                        {"route": "/item", "is_route_dynamic": false, "method": "post",
+                            "request_body": {"name": "string"}},
+                        {"route": "/item/{id}", "is_route_dynamic": true, "method": "get",
+                            "response": {"name": "string"}}
+                    ]"#,
+                )
+                .unwrap(),
+            ),
+            frontend_code: None,
+            database_schema: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn tests_frontend_developer_fixes_unused_endpoints() {
+        let full_page: &str = concat!(
+            "<!DOCTYPE html>\n<html><body><script>\n",
+            "fetch(API_BASE_URL + \"/item\", { method: \"POST\" });\n",
+            "fetch(`${API_BASE_URL}/item/${id}`);\n",
+            "</script></body></html>"
+        );
+        let provider = Arc::new(
+            ScriptedProvider::new()
+                .with_response("print_frontend_code", PARTIAL_PAGE)
+                .with_response("print_improved_frontend_code", PARTIAL_PAGE)
+                .with_response("print_fixed_frontend_code", full_page),
+        );
+
+        let mut factsheet: FactSheet = test_factsheet();
+
+        let workspace: TempWorkspace = TempWorkspace::new("frontend");
+
+        let mut agent: AgentFrontendDeveloper = AgentFrontendDeveloper::new();
+        workspace
+            .scope(async {
+                while agent.attributes.state != AgentState::Finished {
+                    with_provider(provider.clone(), agent.execute_step(&mut factsheet))
+                        .await
+                        .expect("Unable to execute Frontend Developer Agent");
+                }
+            })
+            .await;
+
+        assert_eq!(factsheet.frontend_code.as_deref(), Some(full_page));
+        assert_eq!(
+            fs::read_to_string(workspace.frontend_index_path()).unwrap(),
+            full_page
+        );
+        assert!(
+            frontend_issues(full_page, factsheet.api_endpoint_schema.as_ref().unwrap()).is_empty()
+        );
+        assert_eq!(provider.call_count("print_fixed_frontend_code"), 1);
+        assert_eq!(agent.fix_count, 0);
+    }
+
+    #[tokio::test]
+    async fn tests_frontend_developer_keeps_page_after_max_fixes() {
+        let provider = Arc::new(ScriptedProvider::new().with_response(ANY_FUNCTION, PARTIAL_PAGE));
+        let mut factsheet: FactSheet = test_factsheet();
+        let workspace: TempWorkspace = TempWorkspace::new("frontend_partial");
+
+        let mut agent: AgentFrontendDeveloper = AgentFrontendDeveloper::new();
+        workspace
+            .scope(async {
+                while agent.attributes.state != AgentState::Finished {
+                    with_provider(provider.clone(), agent.execute_step(&mut factsheet))
+                        .await
+                        .expect("Unable to execute Frontend Developer Agent");
+                }
+            })
+            .await;
+
+        assert_eq!(
+            provider.call_count("print_fixed_frontend_code"),
+            MAX_FIX_ATTEMPTS as usize
+        );
+        assert!(agent
+            .frontend_issues
+            .as_deref()
+            .unwrap()
+            .contains("GET /item/{id}"));
+        assert!(workspace.frontend_index_path().exists());
+    }
+}
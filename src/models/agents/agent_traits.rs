@@ -1,15 +1,228 @@
+use crate::errors::GippityError;
 use crate::models::agent_basic::basic_agent::BasicAgent;
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, EnumString, Display)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+    Options,
+}
+
+impl HttpMethod {
+    // Whether requests with this method carry a body
+    pub fn has_body(&self) -> bool {
+        matches!(self, Self::Post | Self::Put | Self::Patch)
+    }
+}
+
+impl TryFrom<String> for HttpMethod {
+    type Error = strum::ParseError;
+
+    fn try_from(method: String) -> Result<Self, Self::Error> {
+        Self::from_str(method.trim())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Static(String),
+    // Named parameter, written {id}, :id or <id> in the route
+    Param(String),
+}
+
+// Whether a parameter names an id: "id" itself, or ending in "_id", "-id" or a capitalised "Id",
+// e.g. "user_id" and "userId" but not "uuid", "guid" or "paid"
+pub fn is_id_param(name: &str) -> bool {
+    let lowercase: String = name.to_ascii_lowercase();
+    if lowercase == "id" || lowercase.ends_with("_id") || lowercase.ends_with("-id") {
+        return true;
+    }
+
+    let mut characters = name.chars().rev();
+    matches!(
+        (characters.next(), characters.next(), characters.next()),
+        (Some('d' | 'D'), Some('I'), Some(before)) if before.is_ascii_lowercase() || before.is_ascii_digit()
+    )
+}
+
+// Url path of a route, kept as written and parsed into its segments
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub struct RoutePath {
+    raw: String,
+    segments: Vec<PathSegment>,
+}
+
+impl RoutePath {
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn params(&self) -> Vec<&str> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                PathSegment::Param(name) => Some(name.as_str()),
+                PathSegment::Static(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        !self.params().is_empty()
+    }
+
+    // Path with every parameter replaced, e.g. by a sample value or as {name}
+    pub fn fill_params(&self, fill: impl Fn(&str) -> String) -> String {
+        let path: String = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Static(text) => text.clone(),
+                PathSegment::Param(name) => fill(name),
+            })
+            .collect::<Vec<String>>()
+            .join("/");
+        format!("/{}", path)
+    }
+}
+
+impl From<&str> for RoutePath {
+    fn from(raw: &str) -> Self {
+        let segments: Vec<PathSegment> = raw
+            .trim()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                let param: Option<&str> = segment
+                    .strip_prefix('{')
+                    .and_then(|name| name.strip_suffix('}'))
+                    .or_else(|| segment.strip_prefix(':'))
+                    .or_else(|| {
+                        segment
+                            .strip_prefix('<')
+                            .and_then(|name| name.strip_suffix('>'))
+                    });
+
+                match param {
+                    // actix allows a pattern after the name, e.g. {id:\d+}
+                    Some(param) => {
+                        PathSegment::Param(param.split(':').next().unwrap_or(param).to_string())
+                    }
+                    None => PathSegment::Static(segment.to_string()),
+                }
+            })
+            .collect();
+
+        Self {
+            raw: raw.trim().to_string(),
+            segments,
+        }
+    }
+}
+
+impl From<String> for RoutePath {
+    fn from(raw: String) -> Self {
+        Self::from(raw.as_str())
+    }
+}
+
+impl From<RoutePath> for String {
+    fn from(route_path: RoutePath) -> Self {
+        route_path.raw
+    }
+}
+
+impl fmt::Display for RoutePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+// Type hint used as a value in request and response shapes, e.g. {"id": "number"}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeHint {
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl TypeHint {
+    // Recognises the hints models write, including Rust type names
+    pub fn parse(hint: &str) -> Option<Self> {
+        match hint.trim().to_lowercase().as_str() {
+            "string" | "str" | "text" => Some(Self::String),
+            "integer" | "int" | "i32" | "i64" | "u32" | "u64" | "usize" => Some(Self::Integer),
+            "number" | "float" | "f32" | "f64" => Some(Self::Number),
+            "bool" | "boolean" => Some(Self::Boolean),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RouteObject {
-    pub is_route_dynamic: String,
-    pub method: String,
+    #[serde(deserialize_with = "deserialize_lenient_bool")]
+    pub is_route_dynamic: bool,
+    pub method: HttpMethod,
+    // Shape of the body, values are type hints such as "number". Null when there is none
+    #[serde(default, deserialize_with = "deserialize_shape")]
     pub request_body: serde_json::Value,
+    #[serde(default, deserialize_with = "deserialize_shape")]
     pub response: serde_json::Value,
-    pub route: String,
+    pub route: RoutePath,
+}
+
+// Older schemas (and models told to) write booleans as "true" or "false"
+fn deserialize_lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LenientBool {
+        Bool(bool),
+        Text(String),
+    }
+
+    match LenientBool::deserialize(deserializer)? {
+        LenientBool::Bool(value) => Ok(value),
+        LenientBool::Text(text) => match text.trim().to_lowercase().as_str() {
+            "true" | "yes" => Ok(true),
+            "false" | "no" => Ok(false),
+            other => Err(serde::de::Error::custom(format!(
+                "expected a boolean, got \"{}\"",
+                other
+            ))),
+        },
+    }
+}
+
+// "None" and "not_provided" stand for no body at all
+fn deserialize_shape<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<serde_json::Value, D::Error> {
+    let shape: serde_json::Value = serde_json::Value::deserialize(deserializer)?;
+    match &shape {
+        serde_json::Value::String(text)
+            if matches!(
+                text.trim().to_lowercase().as_str(),
+                "" | "none" | "null" | "not_provided"
+            ) =>
+        {
+            Ok(serde_json::Value::Null)
+        }
+        _ => Ok(shape),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
@@ -19,6 +232,69 @@ pub struct ProjectScope {
     pub is_external_urls_required: bool,
 }
 
+// Storage class of a column. Models may also write SQL or Rust type names
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+    Boolean,
+}
+
+impl ColumnType {
+    pub fn parse(name: &str) -> Option<Self> {
+        let name: String = name.trim().to_lowercase();
+        // Sizes such as VARCHAR(255) do not matter to SQLite
+        let base: &str = name.split('(').next().unwrap_or_default().trim();
+
+        match base {
+            "real" | "double" | "decimal" | "numeric" => Some(Self::Real),
+            "varchar" | "char" | "datetime" | "timestamp" | "date" | "uuid" => Some(Self::Text),
+            _ => match TypeHint::parse(base)? {
+                TypeHint::String => Some(Self::Text),
+                TypeHint::Integer => Some(Self::Integer),
+                TypeHint::Number => Some(Self::Real),
+                TypeHint::Boolean => Some(Self::Boolean),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for ColumnType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::parse(&name).ok_or_else(|| format!("unknown column type \"{}\"", name))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ColumnDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    #[serde(default)]
+    pub nullable: bool,
+    #[serde(default)]
+    pub primary_key: bool,
+    // Table whose primary key this column refers to
+    #[serde(default)]
+    pub references: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TableDefinition {
+    pub name: String,
+    pub columns: Vec<ColumnDefinition>,
+}
+
+// Relational schema the backend stores its data in
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DatabaseSchema {
+    pub tables: Vec<TableDefinition>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FactSheet {
     pub project_description: String,
@@ -26,15 +302,76 @@ pub struct FactSheet {
     pub external_urls: Option<Vec<String>>,
     pub backend_code: Option<String>,
     pub api_endpoint_schema: Option<Vec<RouteObject>>,
+    // Static index.html calling the backend's endpoints
+    pub frontend_code: Option<String>,
+    // Only designed when the project needs CRUD
+    pub database_schema: Option<DatabaseSchema>,
 }
 
 #[async_trait]
-pub trait SpecialFunctions: Debug {
+pub trait SpecialFunctions: Debug + Send {
     // Used to that manager can get attributes from Agents
     fn get_attributes_from_agent(&self) -> &BasicAgent;
-    // This function will allow agents to execute their logic
-    async fn execute(
-        &mut self,
-        factsheet: &mut FactSheet,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    // This function will allow agents to execute their logic, one state at a time
+    async fn execute_step(&mut self, factsheet: &mut FactSheet) -> Result<(), GippityError>;
+    // Snapshot of the agent stored in run checkpoints
+    fn checkpoint(&self) -> Result<serde_json::Value, GippityError>;
+    // Brings the agent back to a snapshot taken by checkpoint
+    fn restore(&mut self, checkpoint: serde_json::Value) -> Result<(), GippityError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_recognises_id_params() {
+        for name in [
+            "id", "ID", "user_id", "item-id", "userId", "userID", "item2Id",
+        ] {
+            assert!(is_id_param(name), "{} is an id", name);
+        }
+        for name in ["uuid", "guid", "GUID", "paid", "valid", "Id_card", "name"] {
+            assert!(!is_id_param(name), "{} is not an id", name);
+        }
+    }
+
+    #[test]
+    fn tests_reads_stringly_route_objects() {
+        let api_schema: &str = r#"[
+            {
+                "route": "/item/{id}",
+                "is_route_dynamic": "true",
+                "method": "GET",
+                "request_body": "None",
+                "response": {"id": "number", "name": "string", "completed": "bool"}
+            },
+            {
+                "route": "/users/:user_id/items/<item_id>",
+                "is_route_dynamic": true,
+                "method": "delete",
+                "request_body": null,
+                "response": "not_provided"
+            }
+        ]"#;
+
+        let routes: Vec<RouteObject> = serde_json::from_str(api_schema).unwrap();
+
+        assert!(routes[0].is_route_dynamic);
+        assert_eq!(routes[0].method, HttpMethod::Get);
+        assert_eq!(routes[0].request_body, serde_json::Value::Null);
+        assert_eq!(routes[0].route.params(), vec!["id"]);
+        assert_eq!(routes[1].response, serde_json::Value::Null);
+        assert_eq!(routes[1].route.params(), vec!["user_id", "item_id"]);
+        assert_eq!(
+            routes[1].route.fill_params(|name| format!("{{{}}}", name)),
+            "/users/{user_id}/items/{item_id}"
+        );
+
+        // Written back typed, the way it was routed
+        let written: serde_json::Value = serde_json::to_value(&routes[0]).unwrap();
+        assert_eq!(written["is_route_dynamic"], serde_json::Value::Bool(true));
+        assert_eq!(written["method"], "get");
+        assert_eq!(written["route"], "/item/{id}");
+    }
 }
@@ -1,3 +1,5 @@
 pub mod agent_architect;
 pub mod agent_backend;
+pub mod agent_database;
+pub mod agent_frontend;
 pub mod agent_traits;
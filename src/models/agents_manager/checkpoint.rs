@@ -0,0 +1,132 @@
+use crate::errors::GippityError;
+use crate::models::agent_basic::basic_agent::BasicAgent;
+use crate::models::agents::agent_traits::FactSheet;
+use crate::models::general::usage::UsageRecord;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+// Snapshot of one agent of the pipeline
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentCheckpoint {
+    pub position: String,
+    pub agent: serde_json::Value,
+}
+
+// Everything needed to pick a run up where it stopped
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunCheckpoint {
+    pub manager: BasicAgent,
+    pub factsheet: FactSheet,
+    pub agents: Vec<AgentCheckpoint>,
+    // Index of the agent working on the project next
+    pub next_agent: usize,
+    // LLM calls already paid for, so budgets keep counting after a resume
+    pub usage: Vec<UsageRecord>,
+}
+
+impl RunCheckpoint {
+    pub fn path(run_dir: &Path) -> PathBuf {
+        run_dir.join(CHECKPOINT_FILE)
+    }
+
+    // Writes the checkpoint next to the previous one and swaps them, so a crash
+    // while saving never leaves a half written file behind
+    pub fn save(&self, run_dir: &Path) -> Result<PathBuf, GippityError> {
+        let path: PathBuf = Self::path(run_dir);
+        let tmp_path: PathBuf = run_dir.join(format!("{}.tmp", CHECKPOINT_FILE));
+
+        fs::create_dir_all(run_dir)?;
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(path)
+    }
+
+    pub fn load(run_dir: &Path) -> Result<Self, GippityError> {
+        let path: PathBuf = Self::path(run_dir);
+        let checkpoint_str: String = fs::read_to_string(&path).map_err(|e| {
+            GippityError::Config(format!("No checkpoint at {}: {}", path.display(), e))
+        })?;
+
+        Ok(serde_json::from_str(&checkpoint_str)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::workspace::TempWorkspace;
+    use crate::models::agent_basic::basic_agent::AgentState;
+    use crate::models::agents::agent_architect::AgentSolutionArchitect;
+    use crate::models::agents::agent_traits::SpecialFunctions;
+    use crate::models::general::llm::Usage;
+
+    #[test]
+    fn tests_saves_and_loads_checkpoint() {
+        let temp_dir: TempWorkspace = TempWorkspace::new("checkpoint");
+        let run_dir: PathBuf = temp_dir.root.clone();
+
+        let architect: AgentSolutionArchitect = AgentSolutionArchitect::new();
+        let checkpoint: RunCheckpoint = RunCheckpoint {
+            manager: BasicAgent {
+                objective: "Manage agents".to_string(),
+                position: "Project Manager".to_string(),
+                state: AgentState::Working,
+                memory: vec![],
+            },
+            factsheet: FactSheet {
+                project_description: "build a website that tracks forex prices".to_string(),
+                project_scope: None,
+                external_urls: None,
+                backend_code: Some("fn main() {}".to_string()),
+                api_endpoint_schema: None,
+                frontend_code: None,
+                database_schema: None,
+            },
+            agents: vec![AgentCheckpoint {
+                position: "Solutions Architect".to_string(),
+                agent: architect.checkpoint().unwrap(),
+            }],
+            next_agent: 1,
+            usage: vec![UsageRecord::new(
+                "Solutions Architect",
+                "print_project_scope",
+                "gpt-4o",
+                Usage {
+                    prompt_tokens: 100,
+                    completion_tokens: 20,
+                },
+            )],
+        };
+
+        checkpoint
+            .save(&run_dir)
+            .expect("Failed to save checkpoint");
+        let loaded: RunCheckpoint = RunCheckpoint::load(&run_dir).expect("Failed to load");
+
+        assert_eq!(loaded.factsheet, checkpoint.factsheet);
+        assert_eq!(loaded.manager.state, AgentState::Working);
+        assert_eq!(loaded.next_agent, 1);
+        assert_eq!(loaded.usage, checkpoint.usage);
+
+        let mut restored: AgentSolutionArchitect = AgentSolutionArchitect::new();
+        restored
+            .restore(loaded.agents[0].agent.clone())
+            .expect("Failed to restore agent");
+        assert_eq!(
+            restored.get_attributes_from_agent().position,
+            "Solutions Architect"
+        );
+    }
+
+    #[test]
+    fn tests_reports_missing_checkpoint() {
+        let res: Result<RunCheckpoint, GippityError> =
+            RunCheckpoint::load(Path::new("/nonexistent/run-0"));
+        assert!(matches!(res, Err(GippityError::Config(_))));
+    }
+}
@@ -1,40 +1,53 @@
-use crate::ai_functions::aifunc_architect::print_project_scope;
+use crate::errors::GippityError;
 use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agent_basic::basic_traits::BasicTraits;
 use crate::models::agents::agent_traits::{FactSheet, SpecialFunctions};
 
 use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+use crate::helpers::command_line::{PrintCommand, Verbosity};
 use crate::helpers::general::ai_task_request;
+use crate::helpers::workspace::Workspace;
 use crate::models::agents::agent_architect::AgentSolutionArchitect;
-use crate::models::general::llm::Message;
+use crate::models::agents::agent_backend::AgentBackendDeveloper;
+use crate::models::agents::agent_database::AgentDatabaseDesigner;
+use crate::models::agents::agent_frontend::AgentFrontendDeveloper;
+use crate::models::agents_manager::checkpoint::{AgentCheckpoint, RunCheckpoint};
+use crate::models::general::usage::{restore_usage, usage_records, RunReport};
+
+use std::path::{Path, PathBuf};
+use std::process;
+use tokio::signal;
 
 #[derive(Debug)]
 pub struct ManagingAgent {
     attributes: BasicAgent,
     factsheet: FactSheet,
     agents: Vec<Box<dyn SpecialFunctions>>,
+    // Index of the agent working on the project next
+    next_agent: usize,
+    // Where the checkpoints of this run are written
+    run_dir: PathBuf,
 }
 
 impl ManagingAgent {
-    pub async fn new(usr_req: String) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(usr_req: String) -> Result<Self, GippityError> {
         let position: String = "Project Manager".to_string();
 
-        let attributes: BasicAgent = BasicAgent {
-            objective: "Manage agents who are building an excellent website for the user"
-                .to_string(),
-            position: position.clone(),
-            state: AgentState::Discovery,
-            memory: vec![],
-        };
+        let mut attributes: BasicAgent = BasicAgent::new(
+            "Manage agents who are building an excellent website for the user".to_string(),
+            position,
+        );
 
         let project_description: String = ai_task_request(
             usr_req,
-            &position,
+            &mut attributes,
             get_function_string!(convert_user_input_to_goal),
             convert_user_input_to_goal,
         )
-        .await;
+        .await?;
 
         let agents: Vec<Box<dyn SpecialFunctions>> = vec![];
+        let run_dir: PathBuf = Workspace::current()?.new_run_dir()?;
 
         let factsheet: FactSheet = FactSheet {
             project_description,
@@ -42,12 +55,237 @@ impl ManagingAgent {
             external_urls: None,
             backend_code: None,
             api_endpoint_schema: None,
+            frontend_code: None,
+            database_schema: None,
         };
 
-        Ok(Self {
+        let managing_agent: Self = Self {
             attributes,
             factsheet,
             agents,
-        })
+            next_agent: 0,
+            run_dir,
+        };
+
+        // The project description is the first thing worth keeping
+        managing_agent.save_checkpoint()?;
+        Ok(managing_agent)
+    }
+
+    // Picks up a run from the checkpoint in its directory
+    pub fn resume(run_dir: &Path) -> Result<Self, GippityError> {
+        let checkpoint: RunCheckpoint = RunCheckpoint::load(run_dir)?;
+
+        let mut managing_agent: Self = Self {
+            attributes: checkpoint.manager,
+            factsheet: checkpoint.factsheet,
+            agents: vec![],
+            next_agent: checkpoint.next_agent,
+            run_dir: run_dir.to_path_buf(),
+        };
+        managing_agent.create_agents();
+
+        // Checkpoints written before any agent existed only hold the fact sheet
+        if !checkpoint.agents.is_empty() {
+            if checkpoint.agents.len() != managing_agent.agents.len() {
+                return Err(GippityError::Config(format!(
+                    "Checkpoint has {} agents but this pipeline has {}",
+                    checkpoint.agents.len(),
+                    managing_agent.agents.len()
+                )));
+            }
+
+            for (agent, agent_checkpoint) in managing_agent.agents.iter_mut().zip(checkpoint.agents)
+            {
+                let position: &str = &agent.get_attributes_from_agent().position;
+                if position != agent_checkpoint.position {
+                    return Err(GippityError::Config(format!(
+                        "Checkpoint expects {} where this pipeline has {}",
+                        agent_checkpoint.position, position
+                    )));
+                }
+                agent.restore(agent_checkpoint.agent)?;
+            }
+        }
+
+        restore_usage(checkpoint.usage);
+
+        let resume_msg: String = format!("Resuming run from {}", run_dir.display());
+        PrintCommand::AICall.print_agent_message(&managing_agent.attributes.position, &resume_msg);
+        Ok(managing_agent)
+    }
+
+    // Writes the fact sheet, every agent and the pipeline position to the run directory
+    fn save_checkpoint(&self) -> Result<PathBuf, GippityError> {
+        let agents: Vec<AgentCheckpoint> = self
+            .agents
+            .iter()
+            .map(|agent| {
+                Ok(AgentCheckpoint {
+                    position: agent.get_attributes_from_agent().position.clone(),
+                    agent: agent.checkpoint()?,
+                })
+            })
+            .collect::<Result<Vec<AgentCheckpoint>, GippityError>>()?;
+
+        let checkpoint: RunCheckpoint = RunCheckpoint {
+            manager: self.attributes.clone(),
+            factsheet: self.factsheet.clone(),
+            agents,
+            next_agent: self.next_agent,
+            usage: usage_records(),
+        };
+        checkpoint.save(&self.run_dir)
+    }
+
+    fn add_agent(&mut self, agent: Box<dyn SpecialFunctions>) {
+        self.agents.push(agent);
+    }
+
+    // Registers the agents in the order they should work on the project
+    fn create_agents(&mut self) {
+        self.add_agent(Box::new(AgentSolutionArchitect::new()));
+        self.add_agent(Box::new(AgentDatabaseDesigner::new()));
+        self.add_agent(Box::new(AgentBackendDeveloper::new()));
+        self.add_agent(Box::new(AgentFrontendDeveloper::new()));
+    }
+
+    // Runs every agent against the shared fact sheet, then reports the LLM usage
+    pub async fn execute_project(&mut self) -> Result<(), GippityError> {
+        if self.agents.is_empty() {
+            self.create_agents();
+        }
+        self.attributes.state = AgentState::Working;
+
+        // The last checkpoint survives Ctrl-C, so the run can simply stop
+        let interrupt_watcher = tokio::spawn(exit_on_interrupt(
+            self.attributes.position.clone(),
+            self.run_dir.clone(),
+        ));
+
+        let execution_res: Result<(), GippityError> = self.run_agents().await;
+        interrupt_watcher.abort();
+
+        if execution_res.is_err() {
+            let resume_msg: String = format!(
+                "Progress is saved in {}, resume the run from there",
+                self.run_dir.display()
+            );
+            PrintCommand::Issue.print_agent_message(&self.attributes.position, &resume_msg);
+        }
+
+        if Verbosity::current() > Verbosity::Quiet {
+            PrintCommand::AICall
+                .print_agent_message(&self.attributes.position, "Run finished. LLM usage:");
+            println!("{}", RunReport::current());
+        }
+
+        execution_res
+    }
+
+    // Moves the agents forward one step at a time, checkpointing after every step
+    async fn run_agents(&mut self) -> Result<(), GippityError> {
+        self.save_checkpoint()?;
+
+        while self.next_agent < self.agents.len() {
+            let agent: &mut Box<dyn SpecialFunctions> = &mut self.agents[self.next_agent];
+
+            if *agent.get_attributes_from_agent().get_state() == AgentState::Finished {
+                let finished_msg: String = format!(
+                    "{} finished its work",
+                    agent.get_attributes_from_agent().position
+                );
+                PrintCommand::AICall.print_agent_message(&self.attributes.position, &finished_msg);
+
+                self.next_agent += 1;
+                self.save_checkpoint()?;
+                continue;
+            }
+
+            if let Err(e) = agent.execute_step(&mut self.factsheet).await {
+                let err_msg: String = format!(
+                    "{} could not complete its work: {}",
+                    agent.get_attributes_from_agent().position,
+                    e
+                );
+                PrintCommand::Issue.print_agent_message(&self.attributes.position, &err_msg);
+                return Err(e);
+            }
+
+            self.save_checkpoint()?;
+        }
+
+        self.attributes.state = AgentState::Finished;
+        self.save_checkpoint()?;
+        Ok(())
+    }
+}
+
+// Stops the process on Ctrl-C, pointing at the checkpoint to resume from
+async fn exit_on_interrupt(position: String, run_dir: PathBuf) {
+    if signal::ctrl_c().await.is_ok() {
+        let interrupt_msg: String = format!(
+            "Interrupted. Progress is saved in {}, resume the run from there",
+            run_dir.display()
+        );
+        PrintCommand::Issue.print_agent_message(&position, &interrupt_msg);
+        process::exit(130);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::providers::scripted::ScriptedProvider;
+    use crate::apis::providers::with_provider;
+    use crate::helpers::workspace::TempWorkspace;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tests_managing_agent_defines_goal() {
+        let provider = Arc::new(ScriptedProvider::new().with_response(
+            "convert_user_input_to_goal",
+            "build a website that tracks daily forex prices",
+        ));
+
+        let usr_request: String = "need a site that tells me forex prices".to_string();
+        let workspace: TempWorkspace = TempWorkspace::new("managing_forex");
+        let managing_agent: ManagingAgent = workspace
+            .scope(with_provider(provider, ManagingAgent::new(usr_request)))
+            .await
+            .expect("Error creating Managing Agent");
+
+        assert_eq!(
+            managing_agent.factsheet.project_description,
+            "build a website that tracks daily forex prices"
+        );
+        assert!(managing_agent.agents.is_empty());
+        assert!(managing_agent.run_dir.starts_with(workspace.runs_dir()));
+    }
+
+    #[tokio::test]
+    async fn tests_managing_agent_resumes_from_checkpoint() {
+        let provider = Arc::new(ScriptedProvider::new().with_response(
+            "convert_user_input_to_goal",
+            "build a website that tracks daily crypto prices",
+        ));
+
+        let usr_request: String = "need a site that tells me crypto prices".to_string();
+        let workspace: TempWorkspace = TempWorkspace::new("managing_crypto");
+        let managing_agent: ManagingAgent = workspace
+            .scope(with_provider(provider, ManagingAgent::new(usr_request)))
+            .await
+            .expect("Error creating Managing Agent");
+
+        let resumed: ManagingAgent =
+            ManagingAgent::resume(&managing_agent.run_dir).expect("Error resuming the run");
+
+        assert_eq!(resumed.factsheet, managing_agent.factsheet);
+        assert_eq!(resumed.next_agent, 0);
+        assert_eq!(resumed.agents.len(), 4);
+        assert_eq!(
+            resumed.agents[0].get_attributes_from_agent().state,
+            AgentState::Discovery
+        );
     }
 }
@@ -0,0 +1,258 @@
+use crate::errors::GippityError;
+use crate::models::general::usage::{RunReport, UsageTotals};
+
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+
+// Budget enforced for this process
+static CURRENT_BUDGET: OnceLock<Budget> = OnceLock::new();
+
+// Ceilings for a group of LLM calls. Unset ceilings are not enforced
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct BudgetLimits {
+    pub max_tokens: Option<u64>,
+    pub max_cost_usd: Option<f64>,
+    pub max_calls: Option<u64>,
+}
+
+impl BudgetLimits {
+    fn from_env(prefix: &str) -> Result<Self, GippityError> {
+        let read = |name: &str| -> Option<String> { env::var(format!("{}_{}", prefix, name)).ok() };
+
+        Ok(Self {
+            max_tokens: read("MAX_TOKENS").map(|v| v.trim().parse()).transpose()?,
+            max_cost_usd: read("MAX_COST_USD").map(|v| v.trim().parse()).transpose()?,
+            max_calls: read("MAX_CALLS").map(|v| v.trim().parse()).transpose()?,
+        })
+    }
+
+    // First ceiling already reached by the totals
+    fn breach(&self, scope: &BudgetScope, spent: &UsageTotals) -> Option<BudgetBreach> {
+        let breach = |resource: BudgetResource, limit: f64, spent: f64| BudgetBreach {
+            scope: scope.clone(),
+            resource,
+            limit,
+            spent,
+        };
+
+        if let Some(max_calls) = self.max_calls {
+            if spent.calls >= max_calls {
+                return Some(breach(
+                    BudgetResource::Calls,
+                    max_calls as f64,
+                    spent.calls as f64,
+                ));
+            }
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            if spent.total_tokens() >= max_tokens {
+                let spent_tokens: f64 = spent.total_tokens() as f64;
+                return Some(breach(
+                    BudgetResource::Tokens,
+                    max_tokens as f64,
+                    spent_tokens,
+                ));
+            }
+        }
+        if let Some(max_cost_usd) = self.max_cost_usd {
+            if spent.cost_usd >= max_cost_usd {
+                return Some(breach(
+                    BudgetResource::CostUsd,
+                    max_cost_usd,
+                    spent.cost_usd,
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetScope {
+    Run,
+    Agent(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetResource {
+    Tokens,
+    CostUsd,
+    Calls,
+}
+
+// Which budget was hit and by how much
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetBreach {
+    pub scope: BudgetScope,
+    pub resource: BudgetResource,
+    pub limit: f64,
+    pub spent: f64,
+}
+
+impl fmt::Display for BudgetBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.scope {
+            BudgetScope::Run => write!(f, "Run budget")?,
+            BudgetScope::Agent(position) => write!(f, "{} budget", position)?,
+        }
+
+        match self.resource {
+            BudgetResource::Tokens => write!(
+                f,
+                " of {} tokens reached ({} spent)",
+                self.limit, self.spent
+            ),
+            BudgetResource::CostUsd => write!(
+                f,
+                " of ${:.2} reached (${:.4} spent)",
+                self.limit, self.spent
+            ),
+            BudgetResource::Calls => write!(
+                f,
+                " of {} LLM calls reached ({} made)",
+                self.limit, self.spent
+            ),
+        }
+    }
+}
+
+// Ceilings for the whole run and for every single agent
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    pub run: BudgetLimits,
+    pub per_agent: BudgetLimits,
+}
+
+impl Budget {
+    // Reads GIPPITY_MAX_TOKENS, GIPPITY_MAX_COST_USD, GIPPITY_MAX_CALLS for the run
+    // and GIPPITY_AGENT_MAX_TOKENS, GIPPITY_AGENT_MAX_COST_USD, GIPPITY_AGENT_MAX_CALLS per agent
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        Ok(Self {
+            run: BudgetLimits::from_env("GIPPITY")?,
+            per_agent: BudgetLimits::from_env("GIPPITY_AGENT")?,
+        })
+    }
+
+    // Makes this the budget enforced by the rest of the process. Must happen before first use
+    pub fn init(self) -> Result<&'static Budget, GippityError> {
+        CURRENT_BUDGET
+            .set(self)
+            .map_err(|_| GippityError::Config("Budget is already initialised".to_string()))?;
+        Self::current()
+    }
+
+    // Budget enforced by this process, read from the environment on first use
+    pub fn current() -> Result<&'static Budget, GippityError> {
+        if let Some(budget) = CURRENT_BUDGET.get() {
+            return Ok(budget);
+        }
+
+        let budget: Self = Self::from_env()?;
+        Ok(CURRENT_BUDGET.get_or_init(|| budget))
+    }
+
+    // Fails when the run or the agent already used up its budget
+    pub fn check(&self, report: &RunReport, agent_position: &str) -> Result<(), GippityError> {
+        if let Some(breach) = self.run.breach(&BudgetScope::Run, &report.total) {
+            return Err(GippityError::BudgetExceeded(breach));
+        }
+
+        let agent_totals: UsageTotals = report
+            .by_agent
+            .get(agent_position)
+            .copied()
+            .unwrap_or_default();
+        let agent_scope: BudgetScope = BudgetScope::Agent(agent_position.to_string());
+        if let Some(breach) = self.per_agent.breach(&agent_scope, &agent_totals) {
+            return Err(GippityError::BudgetExceeded(breach));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::llm::Usage;
+    use crate::models::general::usage::UsageRecord;
+
+    fn test_report() -> RunReport {
+        let usage: Usage = Usage {
+            prompt_tokens: 1_000,
+            completion_tokens: 500,
+        };
+        RunReport::from_records(&[
+            UsageRecord::new(
+                "Solutions Architect",
+                "print_project_scope",
+                "gpt-4o",
+                usage,
+            ),
+            UsageRecord::new("Backend Developer", "print_fixed_code", "gpt-4o", usage),
+            UsageRecord::new("Backend Developer", "print_fixed_code", "gpt-4o", usage),
+        ])
+    }
+
+    #[test]
+    fn tests_allows_calls_within_budget() {
+        let budget: Budget = Budget {
+            run: BudgetLimits {
+                max_tokens: Some(10_000),
+                max_cost_usd: Some(1.0),
+                max_calls: Some(10),
+            },
+            per_agent: BudgetLimits::default(),
+        };
+
+        assert!(budget.check(&test_report(), "Backend Developer").is_ok());
+    }
+
+    #[test]
+    fn tests_stops_agent_over_its_budget() {
+        let budget: Budget = Budget {
+            run: BudgetLimits::default(),
+            per_agent: BudgetLimits {
+                max_calls: Some(2),
+                ..BudgetLimits::default()
+            },
+        };
+
+        assert!(budget.check(&test_report(), "Solutions Architect").is_ok());
+        match budget.check(&test_report(), "Backend Developer") {
+            Err(GippityError::BudgetExceeded(breach)) => {
+                assert_eq!(
+                    breach.scope,
+                    BudgetScope::Agent("Backend Developer".to_string())
+                );
+                assert_eq!(breach.resource, BudgetResource::Calls);
+            }
+            res => panic!("Unexpected budget check result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn tests_stops_run_over_token_budget() {
+        let budget: Budget = Budget {
+            run: BudgetLimits {
+                max_tokens: Some(4_500),
+                ..BudgetLimits::default()
+            },
+            per_agent: BudgetLimits::default(),
+        };
+
+        let err: GippityError = budget
+            .check(&test_report(), "Solutions Architect")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Budget exceeded: Run budget of 4500 tokens reached (4500 spent)"
+        );
+    }
+}
@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Clone)]
+// Tokens spent on the role and separators of every chat message
+const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Message {
     pub role: String,
     pub content: String,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatCompletion {
     pub model: String,
     pub messages: Vec<Message>,
@@ -26,4 +29,73 @@ pub struct APIChoice {
 #[derive(Debug, Deserialize)]
 pub struct APIResponse {
     pub choices: Vec<APIChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct APIDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct APIStreamChoice {
+    #[serde(default)]
+    pub delta: APIDelta,
+}
+
+// One server-sent event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+pub struct APIStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<APIStreamChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+// Tokens consumed by one call, as reported by the API
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    // Rough count for backends that do not report usage (about 4 characters per token)
+    pub fn estimate(messages: &[Message], completion: &str) -> Self {
+        Self {
+            prompt_tokens: estimate_messages_tokens(messages),
+            completion_tokens: estimate_text_tokens(completion),
+        }
+    }
+}
+
+pub fn estimate_text_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+// Rough size of a prompt, counting the few tokens each message adds for its role
+pub fn estimate_messages_tokens(messages: &[Message]) -> u64 {
+    messages
+        .iter()
+        .map(|message| MESSAGE_OVERHEAD_TOKENS + estimate_text_tokens(&message.content))
+        .sum()
+}
+
+// Answer of a provider along with what it cost
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LlmResponse {
+    pub content: String,
+    pub model: String,
+    pub usage: Option<Usage>,
+}
+
+// Part of an answer received while it is being generated
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatDelta {
+    pub content: String,
+    pub usage: Option<Usage>,
 }
@@ -1 +1,3 @@
+pub mod budget;
 pub mod llm;
+pub mod usage;
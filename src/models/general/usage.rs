@@ -0,0 +1,228 @@
+use crate::models::general::llm::Usage;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::sync::Mutex;
+
+// Every LLM call made by this process
+static USAGE_LEDGER: Mutex<Vec<UsageRecord>> = Mutex::new(Vec::new());
+
+// USD per million prompt and completion tokens. Longest matching model prefix wins
+const MODEL_PRICES: [(&str, f64, f64); 12] = [
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4", 30.0, 60.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+];
+
+// One LLM call attributed to the agent and ai_function that made it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UsageRecord {
+    pub agent_position: String,
+    pub function_name: String,
+    pub model: String,
+    pub usage: Usage,
+    pub cost_usd: f64,
+}
+
+impl UsageRecord {
+    pub fn new(agent_position: &str, function_name: &str, model: &str, usage: Usage) -> Self {
+        Self {
+            agent_position: agent_position.to_string(),
+            function_name: function_name.to_string(),
+            model: model.to_string(),
+            cost_usd: estimate_cost(model, &usage),
+            usage,
+        }
+    }
+}
+
+// Estimated cost of the usage. LLM_PRICE_PROMPT_PER_MTOK and LLM_PRICE_COMPLETION_PER_MTOK
+// override the built in prices, unknown and local models are free
+pub fn estimate_cost(model: &str, usage: &Usage) -> f64 {
+    let (prompt_price, completion_price): (f64, f64) = model_prices(model);
+    (usage.prompt_tokens as f64 * prompt_price + usage.completion_tokens as f64 * completion_price)
+        / 1_000_000.0
+}
+
+fn model_prices(model: &str) -> (f64, f64) {
+    let env_price = |name: &str| -> Option<f64> { env::var(name).ok()?.trim().parse().ok() };
+
+    let (prompt_price, completion_price): (f64, f64) = MODEL_PRICES
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, prompt, completion)| (*prompt, *completion))
+        .unwrap_or((0.0, 0.0));
+
+    (
+        env_price("LLM_PRICE_PROMPT_PER_MTOK").unwrap_or(prompt_price),
+        env_price("LLM_PRICE_COMPLETION_PER_MTOK").unwrap_or(completion_price),
+    )
+}
+
+pub fn record_usage(record: UsageRecord) {
+    USAGE_LEDGER.lock().unwrap().push(record);
+}
+
+pub fn usage_records() -> Vec<UsageRecord> {
+    USAGE_LEDGER.lock().unwrap().clone()
+}
+
+// Puts back the calls of an earlier session, e.g. when resuming a run
+pub fn restore_usage(records: Vec<UsageRecord>) {
+    USAGE_LEDGER.lock().unwrap().extend(records);
+}
+
+// Totals for a group of calls
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.calls += 1;
+        self.prompt_tokens += record.usage.prompt_tokens;
+        self.completion_tokens += record.usage.completion_tokens;
+        self.cost_usd += record.cost_usd;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+// Usage of a whole run grouped by agent, ai_function and model
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RunReport {
+    pub total: UsageTotals,
+    pub by_agent: BTreeMap<String, UsageTotals>,
+    pub by_function: BTreeMap<String, UsageTotals>,
+    pub by_model: BTreeMap<String, UsageTotals>,
+}
+
+impl RunReport {
+    pub fn from_records(records: &[UsageRecord]) -> Self {
+        let mut report: Self = Self::default();
+
+        for record in records {
+            report.total.add(record);
+            report
+                .by_agent
+                .entry(record.agent_position.clone())
+                .or_default()
+                .add(record);
+            report
+                .by_function
+                .entry(record.function_name.clone())
+                .or_default()
+                .add(record);
+            report
+                .by_model
+                .entry(record.model.clone())
+                .or_default()
+                .add(record);
+        }
+
+        report
+    }
+
+    // Report of every call made so far by this process
+    pub fn current() -> Self {
+        Self::from_records(&usage_records())
+    }
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_group = |f: &mut fmt::Formatter<'_>,
+                           title: &str,
+                           group: &BTreeMap<String, UsageTotals>|
+         -> fmt::Result {
+            writeln!(f, "{}:", title)?;
+            for (name, totals) in group {
+                writeln!(
+                    f,
+                    "  {:<32} {:>4} calls {:>9} prompt {:>9} completion  ${:.4}",
+                    name,
+                    totals.calls,
+                    totals.prompt_tokens,
+                    totals.completion_tokens,
+                    totals.cost_usd
+                )?;
+            }
+            Ok(())
+        };
+
+        writeln!(f, "LLM usage report")?;
+        write_group(f, "By agent", &self.by_agent)?;
+        write_group(f, "By ai_function", &self.by_function)?;
+        write_group(f, "By model", &self.by_model)?;
+        write!(
+            f,
+            "Total: {} calls, {} tokens ({} prompt, {} completion), estimated cost ${:.4}",
+            self.total.calls,
+            self.total.total_tokens(),
+            self.total.prompt_tokens,
+            self.total.completion_tokens,
+            self.total.cost_usd
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_estimates_cost_by_model_prefix() {
+        let usage: Usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 1_000_000,
+        };
+
+        assert_eq!(estimate_cost("gpt-3.5-turbo-0125", &usage), 2.0);
+        assert_eq!(estimate_cost("gpt-4o-mini", &usage), 0.75);
+        assert_eq!(estimate_cost("llama3", &usage), 0.0);
+    }
+
+    #[test]
+    fn tests_aggregates_run_report() {
+        let usage: Usage = Usage {
+            prompt_tokens: 100,
+            completion_tokens: 50,
+        };
+        let records: Vec<UsageRecord> = vec![
+            UsageRecord::new(
+                "Solutions Architect",
+                "print_project_scope",
+                "gpt-4o",
+                usage,
+            ),
+            UsageRecord::new("Solutions Architect", "print_site_urls", "gpt-4o", usage),
+            UsageRecord::new("Backend Developer", "print_fixed_code", "gpt-4o", usage),
+        ];
+
+        let report: RunReport = RunReport::from_records(&records);
+
+        assert_eq!(report.total.calls, 3);
+        assert_eq!(report.total.total_tokens(), 450);
+        assert_eq!(report.by_agent["Solutions Architect"].calls, 2);
+        assert_eq!(report.by_function["print_fixed_code"].prompt_tokens, 100);
+        assert!(report.to_string().contains("Backend Developer"));
+    }
+}
@@ -0,0 +1,324 @@
+use crate::errors::GippityError;
+use crate::runner::sandbox::{Sandbox, SandboxOutput, Violation};
+
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use strum_macros::{Display, EnumString};
+use tokio::process::Command;
+
+const DEFAULT_BUILD_TIMEOUT_SECS: u64 = 300;
+
+// Most errors handed to the fixer at once, the first ones usually cause the rest
+const MAX_REPORTED_ERRORS: usize = 10;
+
+// Cargo command used to compile the generated project
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, Display)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum BuildCommand {
+    Build,
+    Check,
+}
+
+// One rustc diagnostic, located at its primary span
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub level: String,
+    pub message: String,
+    pub code: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    // Message as rustc prints it, with the offending code underlined
+    pub rendered: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+}
+
+// Outcome of compiling the generated project
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildReport {
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    pub stderr: String,
+    // Binary produced by `cargo build`, check does not produce one
+    pub executable: Option<PathBuf>,
+    // Sandbox limit the build went over
+    pub violation: Option<Violation>,
+}
+
+impl BuildReport {
+    pub fn errors(&self) -> Vec<&Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .collect()
+    }
+
+    // The errors worth showing print_fixed_code, or cargo's own output when rustc said nothing
+    pub fn error_summary(&self) -> String {
+        let errors: Vec<&Diagnostic> = self.errors();
+        if errors.is_empty() {
+            return match self.violation {
+                Some(violation) => format!("The build was {}", violation),
+                None => self.stderr.trim().to_string(),
+            };
+        }
+
+        let mut summary: Vec<String> = errors
+            .iter()
+            .take(MAX_REPORTED_ERRORS)
+            .map(|diagnostic| match &diagnostic.rendered {
+                Some(rendered) => rendered.trim_end().to_string(),
+                None => format!("error: {}", diagnostic.message),
+            })
+            .collect();
+
+        if errors.len() > MAX_REPORTED_ERRORS {
+            summary.push(format!(
+                "... and {} more errors",
+                errors.len() - MAX_REPORTED_ERRORS
+            ));
+        }
+        summary.join("\n\n")
+    }
+}
+
+// Writes backend code into the output project and compiles it
+#[derive(Debug, Clone)]
+pub struct BuildRunner {
+    project_dir: PathBuf,
+    command: BuildCommand,
+    timeout: Duration,
+    sandbox: Sandbox,
+}
+
+impl BuildRunner {
+    pub fn new(
+        project_dir: PathBuf,
+        command: BuildCommand,
+        timeout: Duration,
+        sandbox: Sandbox,
+    ) -> Self {
+        Self {
+            project_dir,
+            command,
+            timeout,
+            sandbox,
+        }
+    }
+
+    // Reads GIPPITY_BUILD_COMMAND (build | check, defaults to build)
+    // and GIPPITY_BUILD_TIMEOUT_SECS (defaults to 300)
+    pub fn from_env(project_dir: PathBuf) -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let command: BuildCommand = match env::var("GIPPITY_BUILD_COMMAND") {
+            Ok(command_str) => BuildCommand::from_str(command_str.trim())?,
+            Err(_) => BuildCommand::Build,
+        };
+        let timeout_secs: u64 = match env::var("GIPPITY_BUILD_TIMEOUT_SECS") {
+            Ok(timeout_str) => timeout_str.trim().parse()?,
+            Err(_) => DEFAULT_BUILD_TIMEOUT_SECS,
+        };
+
+        Ok(Self::new(
+            project_dir,
+            command,
+            Duration::from_secs(timeout_secs),
+            Sandbox::current()?.clone(),
+        ))
+    }
+
+    // Saves the code as src/main.rs, then builds the project
+    pub async fn build_code(&self, code: &str) -> Result<BuildReport, GippityError> {
+        fs::create_dir_all(self.project_dir.join("src"))?;
+        fs::write(self.project_dir.join("src").join("main.rs"), code)?;
+        self.build().await
+    }
+
+    pub async fn build(&self) -> Result<BuildReport, GippityError> {
+        // Dependencies are fetched outside the sandbox, the build itself gets no network.
+        // A failed fetch shows up again as an error of the offline build
+        if self.sandbox.isolate_network {
+            let unrestricted: Sandbox = Sandbox::disabled();
+            let mut fetch: Command = unrestricted.command("cargo", &self.project_dir, None)?;
+            fetch.arg("fetch");
+            unrestricted.output(fetch, self.timeout).await?;
+        }
+
+        let mut cargo: Command = self.sandbox.command("cargo", &self.project_dir, None)?;
+        cargo
+            .arg(self.command.to_string())
+            .arg("--message-format=json");
+        if self.sandbox.isolate_network {
+            cargo.arg("--offline");
+        }
+
+        let output: SandboxOutput = self.sandbox.output(cargo, self.timeout).await?;
+        Ok(BuildReport {
+            success: output.success,
+            diagnostics: parse_diagnostics(&output.stdout),
+            stderr: output.stderr,
+            executable: parse_executable(&output.stdout),
+            violation: output.violation,
+        })
+    }
+
+    // Path of the compiled server, building it first when needed
+    pub async fn executable(&self) -> Result<PathBuf, GippityError> {
+        let build_runner: Self = Self {
+            command: BuildCommand::Build,
+            ..self.clone()
+        };
+        let build_report: BuildReport = build_runner.build().await?;
+
+        match build_report.executable {
+            Some(executable) if build_report.success => Ok(executable),
+            _ => Err(GippityError::Agent(format!(
+                "Backend did not produce an executable: {}",
+                build_report.error_summary()
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+    executable: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcMessage {
+    message: String,
+    level: String,
+    code: Option<RustcCode>,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+}
+
+// Reads the compiler messages out of cargo's JSON output, skipping the
+// "aborting due to previous errors" style summaries that point at no code
+pub fn parse_diagnostics(cargo_stdout: &str) -> Vec<Diagnostic> {
+    cargo_stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|cargo_message| cargo_message.reason == "compiler-message")
+        .filter_map(|cargo_message| cargo_message.message)
+        .filter(|message| !message.spans.is_empty())
+        .map(|message| {
+            let primary_span: Option<&RustcSpan> = message
+                .spans
+                .iter()
+                .find(|span| span.is_primary)
+                .or(message.spans.first());
+
+            Diagnostic {
+                level: message.level,
+                message: message.message,
+                code: message.code.map(|code| code.code),
+                file: primary_span.map(|span| span.file_name.clone()),
+                line: primary_span.map(|span| span.line_start),
+                column: primary_span.map(|span| span.column_start),
+                rendered: message.rendered,
+            }
+        })
+        .collect()
+}
+
+// Last binary cargo reported building, i.e. the web server
+pub fn parse_executable(cargo_stdout: &str) -> Option<PathBuf> {
+    cargo_stdout
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|cargo_message| cargo_message.reason == "compiler-artifact")
+        .find_map(|cargo_message| cargo_message.executable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::workspace::TempWorkspace;
+
+    fn test_project(name: &str) -> TempWorkspace {
+        let project: TempWorkspace = TempWorkspace::new(&format!("build_{}", name));
+        fs::create_dir_all(&project.root).unwrap();
+        fs::write(
+            project.root.join("Cargo.toml"),
+            "[package]\nname = \"generated\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        project
+    }
+
+    #[test]
+    fn tests_parses_rustc_diagnostics() {
+        let cargo_stdout: &str = r#"{"reason":"compiler-artifact","target":{"name":"serde"}}
+{"reason":"compiler-message","message":{"message":"cannot find value `x` in this scope","code":{"code":"E0425","explanation":null},"level":"error","spans":[{"file_name":"src/main.rs","line_start":2,"column_start":20,"is_primary":true}],"rendered":"error[E0425]: cannot find value `x` in this scope\n"}}
+{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"rendered":"error: aborting due to 1 previous error\n"}}
+{"reason":"build-finished","success":false}"#;
+
+        let diagnostics: Vec<Diagnostic> = parse_diagnostics(cargo_stdout);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0425"));
+        assert_eq!(diagnostics[0].file.as_deref(), Some("src/main.rs"));
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert!(diagnostics[0].is_error());
+    }
+
+    #[tokio::test]
+    async fn tests_builds_code_and_reports_errors() {
+        let project: TempWorkspace = test_project("errors");
+        let project_dir: PathBuf = project.root.clone();
+        let runner: BuildRunner = BuildRunner::new(
+            project_dir.clone(),
+            BuildCommand::Check,
+            Duration::from_secs(120),
+            Sandbox::from_env().unwrap(),
+        );
+
+        let broken: BuildReport = runner
+            .build_code("fn main() {\n    println!(\"{}\", x);\n}\n")
+            .await
+            .expect("Failed to run cargo");
+        assert!(!broken.success);
+        assert_eq!(broken.errors().len(), 1);
+        assert_eq!(broken.errors()[0].line, Some(2));
+        assert!(broken.error_summary().contains("E0425"));
+
+        let fixed: BuildReport = runner
+            .build_code("fn main() {\n    println!(\"{}\", 1);\n}\n")
+            .await
+            .expect("Failed to run cargo");
+        assert!(fixed.success);
+        assert!(fixed.errors().is_empty());
+
+        let executable: PathBuf = runner.executable().await.expect("Failed to build binary");
+        assert!(executable.ends_with("generated"));
+    }
+}
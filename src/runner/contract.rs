@@ -0,0 +1,563 @@
+use crate::apis::cassette::active_cassette;
+use crate::errors::GippityError;
+use crate::models::agents::agent_traits::{
+    is_id_param, HttpMethod, RouteObject, RoutePath, TypeHint,
+};
+use crate::runner::sandbox::{kill_process_group, Sandbox, Violation};
+
+use dotenv::dotenv;
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+use tokio::time::{self, Instant};
+
+const DEFAULT_SERVER_PORT: u16 = 8080;
+const DEFAULT_STARTUP_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 5;
+
+// Time between two attempts to reach a booting server
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Server output kept next to the build artifacts, shown when it fails to boot
+const SERVER_LOG_FILE: &str = "target/gippity-server.log";
+const SERVER_LOG_TAIL_LINES: usize = 20;
+
+// Request sent to one route of the running server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RouteRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<Value>,
+}
+
+// Outcome of contract testing one route
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RouteResult {
+    pub method: String,
+    pub route: String,
+    pub status: Option<u16>,
+    // Why the route broke its contract, empty when it passed
+    pub failures: Vec<String>,
+}
+
+impl RouteResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for RouteResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict: &str = if self.passed() { "PASS" } else { "FAIL" };
+        write!(
+            f,
+            "{} {} {}",
+            verdict,
+            self.method.to_uppercase(),
+            self.route
+        )?;
+        if let Some(status) = self.status {
+            write!(f, " ({})", status)?;
+        }
+        if !self.passed() {
+            write!(f, ": {}", self.failures.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+// Verdict per route and the sandbox limit the server went over, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractReport {
+    pub route_results: Vec<RouteResult>,
+    pub violation: Option<Violation>,
+}
+
+// Compiled backend listening on a local port
+#[derive(Debug)]
+pub struct RunningServer {
+    child: Child,
+    base_url: String,
+    log_path: PathBuf,
+    sandbox: Sandbox,
+}
+
+impl RunningServer {
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Stops the server and everything it started. A server that already
+    // stopped by itself is checked for the sandbox limit it went over
+    pub async fn shutdown(mut self) -> Result<Option<Violation>, GippityError> {
+        if let Some(exit_status) = self.child.try_wait()? {
+            return Ok(self
+                .sandbox
+                .violation(exit_status, &log_tail(&self.log_path)));
+        }
+
+        if let Some(pid) = self.child.id() {
+            kill_process_group(pid);
+        }
+        self.child.wait().await?;
+        Ok(None)
+    }
+}
+
+// Starts the compiled backend and checks every route against its RouteObject
+#[derive(Debug, Clone)]
+pub struct ContractTester {
+    project_dir: PathBuf,
+    port: u16,
+    startup_timeout: Duration,
+    request_timeout: Duration,
+    sandbox: Sandbox,
+}
+
+impl ContractTester {
+    pub fn new(
+        project_dir: PathBuf,
+        port: u16,
+        startup_timeout: Duration,
+        request_timeout: Duration,
+        sandbox: Sandbox,
+    ) -> Self {
+        Self {
+            project_dir,
+            port,
+            startup_timeout,
+            request_timeout,
+            sandbox,
+        }
+    }
+
+    // Reads GIPPITY_SERVER_PORT (defaults to 8080, the port of the code template),
+    // GIPPITY_SERVER_STARTUP_SECS (defaults to 30) and GIPPITY_REQUEST_TIMEOUT_SECS (defaults to 5)
+    pub fn from_env(project_dir: PathBuf) -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let port: u16 = match env::var("GIPPITY_SERVER_PORT") {
+            Ok(port_str) => port_str.trim().parse()?,
+            Err(_) => DEFAULT_SERVER_PORT,
+        };
+        let startup_secs: u64 = match env::var("GIPPITY_SERVER_STARTUP_SECS") {
+            Ok(startup_str) => startup_str.trim().parse()?,
+            Err(_) => DEFAULT_STARTUP_TIMEOUT_SECS,
+        };
+        let request_secs: u64 = match env::var("GIPPITY_REQUEST_TIMEOUT_SECS") {
+            Ok(request_str) => request_str.trim().parse()?,
+            Err(_) => DEFAULT_REQUEST_TIMEOUT_SECS,
+        };
+
+        Ok(Self::new(
+            project_dir,
+            port,
+            Duration::from_secs(startup_secs),
+            Duration::from_secs(request_secs),
+            Sandbox::current()?.clone(),
+        ))
+    }
+
+    // Address the server listens on, also where a frontend reaches it
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    // Launches the server, tests every route, then shuts the server down
+    pub async fn test_routes(
+        &self,
+        executable: &Path,
+        routes: &[RouteObject],
+    ) -> Result<ContractReport, GippityError> {
+        let server: RunningServer = self.launch(executable).await?;
+        let client: Client = Client::builder().timeout(self.request_timeout).build()?;
+
+        let route_results: Vec<RouteResult> =
+            check_routes(&client, server.base_url(), routes).await;
+
+        let violation: Option<Violation> = server.shutdown().await?;
+        Ok(ContractReport {
+            route_results,
+            violation,
+        })
+    }
+
+    // Starts the server and waits until it accepts connections
+    pub async fn launch(&self, executable: &Path) -> Result<RunningServer, GippityError> {
+        // Another process answering on the port would pass every readiness check
+        if TcpStream::connect(("127.0.0.1", self.port)).await.is_ok() {
+            return Err(GippityError::Agent(format!(
+                "Port {} is already in use, set GIPPITY_SERVER_PORT to a free port",
+                self.port
+            )));
+        }
+
+        let log_path: PathBuf = self.project_dir.join(SERVER_LOG_FILE);
+        if let Some(log_dir) = log_path.parent() {
+            fs::create_dir_all(log_dir)?;
+        }
+        let log_file: File = File::create(&log_path)?;
+
+        // An isolated server is reached through its port forwarded to the host's localhost
+        let mut command: Command =
+            self.sandbox
+                .command(executable, &self.project_dir, Some(self.port))?;
+        let child: Child = command
+            .env("PORT", self.port.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(log_file.try_clone()?))
+            .stderr(Stdio::from(log_file))
+            .spawn()?;
+
+        let mut server: RunningServer = RunningServer {
+            child,
+            base_url: self.base_url(),
+            log_path: log_path.clone(),
+            sandbox: self.sandbox.clone(),
+        };
+
+        let deadline: Instant = Instant::now() + self.startup_timeout;
+        loop {
+            if let Some(exit_status) = server.child.try_wait()? {
+                let log: String = log_tail(&log_path);
+                let exit_reason: String = match self.sandbox.violation(exit_status, &log) {
+                    Some(violation) => violation.to_string(),
+                    None => format!("exited with {}", exit_status),
+                };
+                return Err(GippityError::Agent(format!(
+                    "Web server {} before accepting connections:\n{}",
+                    exit_reason, log
+                )));
+            }
+
+            if TcpStream::connect(("127.0.0.1", self.port)).await.is_ok() {
+                return Ok(server);
+            }
+
+            if Instant::now() >= deadline {
+                server.shutdown().await?;
+                return Err(GippityError::Agent(format!(
+                    "Web server did not listen on port {} within {:?}:\n{}",
+                    self.port,
+                    self.startup_timeout,
+                    log_tail(&log_path)
+                )));
+            }
+
+            time::sleep(READINESS_POLL_INTERVAL).await;
+        }
+    }
+}
+
+// Checks the routes against a running server. Creating routes go first
+// and deleting ones last, so sample ids have a chance to exist
+pub async fn check_routes(
+    client: &Client,
+    base_url: &str,
+    routes: &[RouteObject],
+) -> Vec<RouteResult> {
+    let mut ordered_routes: Vec<&RouteObject> = routes.iter().collect();
+    ordered_routes.sort_by_key(|route_object| method_order(route_object.method));
+
+    let mut route_results: Vec<RouteResult> = vec![];
+    for route_object in ordered_routes {
+        route_results.push(check_route(client, base_url, route_object).await);
+    }
+    route_results
+}
+
+async fn check_route(client: &Client, base_url: &str, route_object: &RouteObject) -> RouteResult {
+    let route_request: RouteRequest = RouteRequest {
+        method: route_object.method.to_string().to_uppercase(),
+        url: format!("{}{}", base_url, sample_route(&route_object.route)),
+        body: match &route_object.request_body {
+            Value::Null => None,
+            request_body if route_object.method.has_body() => Some(sample_value(request_body)),
+            _ => None,
+        },
+    };
+
+    let route_check = contract_check(client, route_object, &route_request);
+
+    // Records or replays the check when a cassette is in use
+    let checked: Result<RouteResult, GippityError> = match active_cassette() {
+        Ok(Some(cassette)) => cassette.route_check(&route_request, route_check).await,
+        Ok(None) => route_check.await,
+        Err(e) => Err(e),
+    };
+
+    checked.unwrap_or_else(|e| RouteResult {
+        method: route_object.method.to_string(),
+        route: route_object.route.to_string(),
+        status: None,
+        failures: vec![e.to_string()],
+    })
+}
+
+async fn contract_check(
+    client: &Client,
+    route_object: &RouteObject,
+    route_request: &RouteRequest,
+) -> Result<RouteResult, GippityError> {
+    let method: Method = Method::from_bytes(route_request.method.as_bytes())
+        .map_err(|_| GippityError::Agent(format!("Unknown HTTP method {}", route_object.method)))?;
+
+    let mut request = client.request(method, &route_request.url);
+    if let Some(body) = &route_request.body {
+        request = request.json(body);
+    }
+
+    let response: reqwest::Response = request.send().await?;
+    let status: u16 = response.status().as_u16();
+    let body: String = response.text().await?;
+
+    let mut route_result: RouteResult = RouteResult {
+        method: route_object.method.to_string(),
+        route: route_object.route.to_string(),
+        status: Some(status),
+        failures: vec![],
+    };
+
+    // A sample id missing from a fresh server is not a broken contract
+    if status == 404 && route_object.route.is_dynamic() {
+        return Ok(route_result);
+    }
+
+    if !(200..300).contains(&status) {
+        route_result
+            .failures
+            .push(format!("expected a 2xx status, got {}", status));
+        return Ok(route_result);
+    }
+
+    // Plain text bodies are compared as JSON strings
+    let actual: Value = serde_json::from_str(&body).unwrap_or(Value::String(body));
+    route_result.failures = shape_mismatches(&route_object.response, &actual, "response");
+    Ok(route_result)
+}
+
+fn method_order(method: HttpMethod) -> u8 {
+    match method {
+        HttpMethod::Post => 0,
+        HttpMethod::Put | HttpMethod::Patch => 2,
+        HttpMethod::Delete => 3,
+        _ => 1,
+    }
+}
+
+// Path with sample values for its parameters: 1 for ids, "sample" otherwise
+pub fn sample_route(route: &RoutePath) -> String {
+    route.fill_params(|name| {
+        if is_id_param(name) {
+            "1".to_string()
+        } else {
+            "sample".to_string()
+        }
+    })
+}
+
+// Kind of JSON value a type hint such as "string" or "u64" stands for
+fn hint_kind(type_name: &str) -> Option<&'static str> {
+    match TypeHint::parse(type_name)? {
+        TypeHint::String => Some("string"),
+        TypeHint::Integer | TypeHint::Number => Some("number"),
+        TypeHint::Boolean => Some("boolean"),
+    }
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// Turns a described request body into one that can be sent, e.g. "string" becomes "sample"
+pub fn sample_value(described: &Value) -> Value {
+    match described {
+        Value::String(type_name) => match TypeHint::parse(type_name) {
+            Some(TypeHint::Integer | TypeHint::Number) => Value::from(1),
+            Some(TypeHint::Boolean) => Value::Bool(true),
+            Some(TypeHint::String) => Value::String("sample".to_string()),
+            None => described.clone(),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(sample_value).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), sample_value(value)))
+                .collect(),
+        ),
+        _ => described.clone(),
+    }
+}
+
+// Differences between the described response and the actual one. Objects must
+// have the described fields, arrays items shaped like the first described one
+pub fn shape_mismatches(expected: &Value, actual: &Value, path: &str) -> Vec<String> {
+    let expected_kind: &str = match expected {
+        Value::Null => return vec![],
+        Value::String(type_name) => hint_kind(type_name).unwrap_or("string"),
+        _ => value_kind(expected),
+    };
+
+    if expected_kind != value_kind(actual) {
+        return vec![format!(
+            "{} should be {} but is {}",
+            path,
+            expected_kind,
+            value_kind(actual)
+        )];
+    }
+
+    match (expected, actual) {
+        (Value::Object(expected_fields), Value::Object(actual_fields)) => expected_fields
+            .iter()
+            .flat_map(|(key, expected_value)| {
+                let field_path: String = format!("{}.{}", path, key);
+                match actual_fields.get(key) {
+                    Some(actual_value) => {
+                        shape_mismatches(expected_value, actual_value, &field_path)
+                    }
+                    None => vec![format!("{} is missing", field_path)],
+                }
+            })
+            .collect(),
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            match (expected_items.first(), actual_items.first()) {
+                (Some(expected_item), Some(actual_item)) => {
+                    shape_mismatches(expected_item, actual_item, &format!("{}[0]", path))
+                }
+                _ => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+fn log_tail(log_path: &Path) -> String {
+    let log: String = fs::read_to_string(log_path).unwrap_or_default();
+    let lines: Vec<&str> = log.lines().collect();
+    lines[lines.len().saturating_sub(SERVER_LOG_TAIL_LINES)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+
+    fn route(method: &str, route: &str, request_body: Value, response: Value) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: route.contains('{'),
+            method: method.parse().unwrap(),
+            request_body,
+            response,
+            route: RoutePath::from(route),
+        }
+    }
+
+    async fn answer(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let body: &str = match (req.method().as_str(), req.uri().path()) {
+            ("GET", "/tasks") => r#"[{"id": 1, "title": "write tests", "done": false}]"#,
+            ("GET", "/tasks/1") => r#"{"id": "1", "title": "write tests"}"#,
+            ("POST", "/tasks") => r#"{"id": 1}"#,
+            _ => return Ok(Response::builder().status(404).body(Body::empty()).unwrap()),
+        };
+        Ok(Response::new(Body::from(body)))
+    }
+
+    #[test]
+    fn tests_samples_routes_and_bodies() {
+        assert_eq!(sample_route(&"/tasks/{id}".into()), "/tasks/1");
+        assert_eq!(
+            sample_route(&"/users/:user_id/posts".into()),
+            "/users/1/posts"
+        );
+        assert_eq!(sample_route(&"/prices/<symbol>".into()), "/prices/sample");
+        assert_eq!(sample_route(&"/sessions/{uuid}".into()), "/sessions/sample");
+
+        assert_eq!(
+            sample_value(&json!({"title": "string", "done": "bool", "priority": "u32"})),
+            json!({"title": "sample", "done": true, "priority": 1})
+        );
+    }
+
+    #[test]
+    fn tests_compares_response_shapes() {
+        let expected: Value = json!([{"id": "number", "title": "string"}]);
+
+        assert!(
+            shape_mismatches(&expected, &json!([{"id": 3, "title": "a"}]), "response").is_empty()
+        );
+        assert!(shape_mismatches(&expected, &json!([]), "response").is_empty());
+        assert_eq!(
+            shape_mismatches(&expected, &json!([{"id": "3"}]), "response"),
+            vec![
+                "response[0].id should be number but is string",
+                "response[0].title is missing"
+            ]
+        );
+        assert!(shape_mismatches(&Value::Null, &json!("Logged in!"), "response").is_empty());
+    }
+
+    #[tokio::test]
+    async fn tests_checks_routes_against_server() {
+        let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(answer)) });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr: SocketAddr = server.local_addr();
+        tokio::spawn(server);
+
+        let routes: Vec<RouteObject> = vec![
+            route(
+                "get",
+                "/tasks",
+                Value::Null,
+                json!([{"id": 1, "title": "string"}]),
+            ),
+            route("get", "/tasks/{id}", Value::Null, json!({"id": "number"})),
+            route(
+                "post",
+                "/tasks",
+                json!({"title": "string"}),
+                json!({"id": "number"}),
+            ),
+            route("delete", "/tasks/{id}", Value::Null, Value::Null),
+            route("get", "/health", Value::Null, Value::Null),
+        ];
+
+        let client: Client = Client::new();
+        let route_results: Vec<RouteResult> =
+            check_routes(&client, &format!("http://{}", addr), &routes).await;
+        let verdicts: Vec<String> = route_results
+            .iter()
+            .map(|result| result.to_string())
+            .collect();
+
+        assert_eq!(
+            verdicts,
+            vec![
+                "PASS POST /tasks (200)",
+                "PASS GET /tasks (200)",
+                "FAIL GET /tasks/{id} (200): response.id should be number but is string",
+                "FAIL GET /health (404): expected a 2xx status, got 404",
+                "PASS DELETE /tasks/{id} (404)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod build;
+pub mod contract;
+pub mod sandbox;
@@ -0,0 +1,640 @@
+use crate::errors::GippityError;
+
+use dotenv::dotenv;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+use tokio::time;
+
+const DEFAULT_CPU_SECS: u64 = 600;
+const DEFAULT_MEMORY_MB: u64 = 8_192;
+
+// Variables generated code and cargo still see, everything else is cleared
+const KEPT_ENV_VARS: [&str; 8] = [
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+    "CARGO_TARGET_DIR",
+];
+
+// Sandbox used by this process
+static CURRENT_SANDBOX: OnceLock<Sandbox> = OnceLock::new();
+
+// Limit the generated code went over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    WallTime(Duration),
+    CpuTime(u64),
+    Memory(u64),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WallTime(limit) => write!(f, "killed after the time limit of {:?}", limit),
+            Self::CpuTime(limit) => write!(f, "killed after the CPU limit of {}s", limit),
+            Self::Memory(limit) => write!(f, "ran out of its memory limit of {} MB", limit),
+        }
+    }
+}
+
+// Output of a command that ran in the sandbox
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub violation: Option<Violation>,
+}
+
+// Restrictions applied while generated code is built and run. On Linux each
+// command gets its own process group, CPU and memory rlimits and, when
+// isolated, a network namespace where only localhost is reachable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sandbox {
+    pub enabled: bool,
+    pub isolate_network: bool,
+    pub cpu_secs: Option<u64>,
+    pub memory_mb: Option<u64>,
+    // Why the network could not be isolated, commands then keep the host network
+    pub isolation_error: Option<String>,
+}
+
+impl Sandbox {
+    // Runs commands as they are, only the time limits of the callers apply
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            isolate_network: false,
+            cpu_secs: None,
+            memory_mb: None,
+            isolation_error: None,
+        }
+    }
+
+    // Reads GIPPITY_SANDBOX and GIPPITY_SANDBOX_NETWORK (true | false, both default to true),
+    // GIPPITY_SANDBOX_CPU_SECS (defaults to 600) and GIPPITY_SANDBOX_MEMORY_MB (defaults to 8192)
+    pub fn from_env() -> Result<Self, GippityError> {
+        dotenv().ok();
+
+        let enabled: bool = match env::var("GIPPITY_SANDBOX") {
+            Ok(enabled_str) => enabled_str.trim().parse()?,
+            Err(_) => true,
+        };
+        if !enabled {
+            return Ok(Self::disabled());
+        }
+
+        let isolate_network: bool = match env::var("GIPPITY_SANDBOX_NETWORK") {
+            Ok(isolate_str) => isolate_str.trim().parse()?,
+            Err(_) => true,
+        };
+        let cpu_secs: u64 = match env::var("GIPPITY_SANDBOX_CPU_SECS") {
+            Ok(cpu_str) => cpu_str.trim().parse()?,
+            Err(_) => DEFAULT_CPU_SECS,
+        };
+        let memory_mb: u64 = match env::var("GIPPITY_SANDBOX_MEMORY_MB") {
+            Ok(memory_str) => memory_str.trim().parse()?,
+            Err(_) => DEFAULT_MEMORY_MB,
+        };
+
+        Ok(Self {
+            enabled,
+            isolate_network,
+            cpu_secs: Some(cpu_secs),
+            memory_mb: Some(memory_mb),
+            isolation_error: None,
+        }
+        .probe())
+    }
+
+    // Sandbox used by this process, read from the environment on first use
+    pub fn current() -> Result<&'static Sandbox, GippityError> {
+        if let Some(sandbox) = CURRENT_SANDBOX.get() {
+            return Ok(sandbox);
+        }
+
+        let sandbox: Self = Self::from_env()?;
+        Ok(CURRENT_SANDBOX.get_or_init(|| sandbox))
+    }
+
+    // Falls back to the host network when the kernel refuses a new namespace,
+    // e.g. in containers without user namespaces
+    fn probe(mut self) -> Self {
+        if !self.isolate_network {
+            return self;
+        }
+
+        let mut probe: std::process::Command = std::process::Command::new("true");
+        probe.stdout(Stdio::null()).stderr(Stdio::null());
+        let probe_res: std::io::Result<ExitStatus> = isolation_hook(&self, None).and_then(|hook| {
+            // SAFETY: the hook only makes async-signal-safe system calls
+            unsafe {
+                std::os::unix::process::CommandExt::pre_exec(&mut probe, hook);
+            }
+            probe.status()
+        });
+
+        if let Err(e) = probe_res {
+            self.isolate_network = false;
+            self.isolation_error = Some(format!("Network isolation is unavailable: {}", e));
+        }
+        self
+    }
+
+    // Command running in its own process group and, when enabled, within the limits.
+    // A forwarded port of the isolated network is reachable on the same localhost port
+    pub fn command(
+        &self,
+        program: impl AsRef<std::ffi::OsStr>,
+        working_dir: &Path,
+        forward_port: Option<u16>,
+    ) -> Result<Command, GippityError> {
+        let mut command: Command = Command::new(program);
+        command
+            .current_dir(working_dir)
+            .process_group(0)
+            .kill_on_drop(true);
+
+        if !self.enabled {
+            return Ok(command);
+        }
+
+        // Temporary files stay inside the project
+        let tmp_dir: PathBuf = working_dir.join("target").join("tmp");
+        fs::create_dir_all(&tmp_dir)?;
+
+        command.env_clear().env("TMPDIR", &tmp_dir);
+        for var in KEPT_ENV_VARS {
+            if let Ok(value) = env::var(var) {
+                command.env(var, value);
+            }
+        }
+
+        let hook = isolation_hook(self, forward_port)?;
+        // SAFETY: the hook only makes async-signal-safe system calls
+        unsafe {
+            command.pre_exec(hook);
+        }
+        Ok(command)
+    }
+
+    // Runs the command to completion, killing its whole process group after the timeout
+    pub async fn output(
+        &self,
+        mut command: Command,
+        timeout: Duration,
+    ) -> Result<SandboxOutput, GippityError> {
+        let child: Child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let pid: Option<u32> = child.id();
+
+        match time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => {
+                let output: std::process::Output = output?;
+                let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+                Ok(SandboxOutput {
+                    success: output.status.success(),
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    violation: self.violation(output.status, &stderr),
+                    stderr,
+                })
+            }
+            Err(_) => {
+                if let Some(pid) = pid {
+                    kill_process_group(pid);
+                }
+                Ok(SandboxOutput {
+                    success: false,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    violation: Some(Violation::WallTime(timeout)),
+                })
+            }
+        }
+    }
+
+    // Limit a finished command went over, judged from how it ended
+    pub fn violation(&self, status: ExitStatus, stderr: &str) -> Option<Violation> {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let (Some(cpu_secs), Some(libc::SIGXCPU)) = (self.cpu_secs, status.signal()) {
+            return Some(Violation::CpuTime(cpu_secs));
+        }
+
+        let out_of_memory: bool = stderr.contains("memory allocation of")
+            || stderr.contains("Cannot allocate memory")
+            || stderr.contains("out of memory");
+        match self.memory_mb {
+            Some(memory_mb) if !status.success() && out_of_memory => {
+                Some(Violation::Memory(memory_mb))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Kills a sandboxed command together with everything it started
+pub fn kill_process_group(pid: u32) {
+    // SAFETY: plain system call, the group id is the pid of its leader
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn isolation_hook(
+    sandbox: &Sandbox,
+    forward_port: Option<u16>,
+) -> std::io::Result<impl FnMut() -> std::io::Result<()> + Send + Sync + 'static> {
+    linux::isolation_hook(sandbox, forward_port)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn isolation_hook(
+    sandbox: &Sandbox,
+    _forward_port: Option<u16>,
+) -> std::io::Result<impl FnMut() -> std::io::Result<()> + Send + Sync + 'static> {
+    if sandbox.isolate_network {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "sandboxing needs Linux",
+        ));
+    }
+    Ok(|| Ok(()))
+}
+
+// Everything here runs between fork and exec, so only system calls are made:
+// no allocation, no locks, no panics
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::Sandbox;
+    use std::ffi::CString;
+    use std::io;
+    use std::mem;
+
+    // Connections the port forwarder serves at once
+    const MAX_FORWARDED_CONNECTIONS: usize = 16;
+
+    pub fn isolation_hook(
+        sandbox: &Sandbox,
+        forward_port: Option<u16>,
+    ) -> io::Result<impl FnMut() -> io::Result<()> + Send + Sync + 'static> {
+        let cpu_secs: Option<u64> = sandbox.cpu_secs;
+        let memory_bytes: Option<u64> = sandbox.memory_mb.map(|mb| mb * 1024 * 1024);
+        let isolate_network: bool = sandbox.isolate_network;
+
+        // Root can create a network namespace directly, anyone else needs a user namespace
+        // mapping their ids onto themselves. The maps are written before fork allocates nothing
+        // SAFETY: plain system calls
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        let user_maps: Option<(CString, CString)> = if uid == 0 {
+            None
+        } else {
+            Some((
+                CString::new(format!("{} {} 1\n", uid, uid))?,
+                CString::new(format!("{} {} 1\n", gid, gid))?,
+            ))
+        };
+
+        Ok(move || {
+            if let Some(cpu_secs) = cpu_secs {
+                // SIGXCPU at the soft limit tells the violation apart from other kills
+                set_rlimit(libc::RLIMIT_CPU, cpu_secs, cpu_secs + 1)?;
+            }
+            if let Some(memory_bytes) = memory_bytes {
+                set_rlimit(libc::RLIMIT_AS, memory_bytes, memory_bytes)?;
+            }
+            if !isolate_network {
+                return Ok(());
+            }
+
+            // Created before unsharing, so it listens on the host's localhost
+            let outer_fd: libc::c_int = match forward_port {
+                Some(_) => check(unsafe {
+                    libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0)
+                })?,
+                None => -1,
+            };
+
+            match &user_maps {
+                None => {
+                    check(unsafe { libc::unshare(libc::CLONE_NEWNET) })?;
+                }
+                Some((uid_map, gid_map)) => {
+                    check(unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) })?;
+                    write_proc_file(c"/proc/self/setgroups", b"deny")?;
+                    write_proc_file(c"/proc/self/uid_map", uid_map.as_bytes())?;
+                    write_proc_file(c"/proc/self/gid_map", gid_map.as_bytes())?;
+                }
+            };
+            bring_loopback_up()?;
+
+            if let Some(port) = forward_port {
+                match unsafe { libc::fork() } {
+                    -1 => return Err(io::Error::last_os_error()),
+                    0 => unsafe {
+                        // The forwarder goes away with the server
+                        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                        // Inherited descriptors include the pipe spawn() waits on until exec
+                        close_inherited_fds(outer_fd);
+                        forward_port_loop(outer_fd, port);
+                        libc::_exit(0);
+                    },
+                    _ => unsafe {
+                        libc::close(outer_fd);
+                    },
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Closes every descriptor above stdio except the one given
+    unsafe fn close_inherited_fds(keep_fd: libc::c_int) {
+        let max_fd: libc::c_int = match libc::sysconf(libc::_SC_OPEN_MAX) {
+            open_max if open_max > 0 => open_max.min(65_536) as libc::c_int,
+            _ => 1_024,
+        };
+        for fd in 3..max_fd {
+            if fd != keep_fd {
+                libc::close(fd);
+            }
+        }
+    }
+
+    fn check(res: libc::c_int) -> io::Result<libc::c_int> {
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(res)
+    }
+
+    fn set_rlimit(resource: libc::__rlimit_resource_t, soft: u64, hard: u64) -> io::Result<()> {
+        let limit: libc::rlimit = libc::rlimit {
+            rlim_cur: soft as libc::rlim_t,
+            rlim_max: hard as libc::rlim_t,
+        };
+        check(unsafe { libc::setrlimit(resource, &limit) })?;
+        Ok(())
+    }
+
+    fn write_proc_file(path: &std::ffi::CStr, contents: &[u8]) -> io::Result<()> {
+        let fd: libc::c_int = check(unsafe { libc::open(path.as_ptr(), libc::O_WRONLY) })?;
+        let written: isize =
+            unsafe { libc::write(fd, contents.as_ptr() as *const libc::c_void, contents.len()) };
+        unsafe { libc::close(fd) };
+        if written == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // A new network namespace starts with its loopback interface down
+    fn bring_loopback_up() -> io::Result<()> {
+        let fd: libc::c_int = check(unsafe {
+            libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0)
+        })?;
+
+        let mut ifr: libc::ifreq = unsafe { mem::zeroed() };
+        for (i, byte) in b"lo".iter().enumerate() {
+            ifr.ifr_name[i] = *byte as libc::c_char;
+        }
+
+        let res: io::Result<libc::c_int> =
+            check(unsafe { libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut ifr) }).and_then(|_| {
+                unsafe { ifr.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short };
+                check(unsafe { libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &ifr) })
+            });
+        unsafe { libc::close(fd) };
+        res.map(|_| ())
+    }
+
+    fn localhost_addr(port: u16) -> libc::sockaddr_in {
+        libc::sockaddr_in {
+            sin_family: libc::AF_INET as libc::sa_family_t,
+            sin_port: port.to_be(),
+            sin_addr: libc::in_addr {
+                s_addr: u32::from_be_bytes([127, 0, 0, 1]).to_be(),
+            },
+            sin_zero: [0; 8],
+        }
+    }
+
+    unsafe fn connect_inner(port: u16) -> libc::c_int {
+        let addr: libc::sockaddr_in = localhost_addr(port);
+        let fd: libc::c_int =
+            libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+        if fd == -1 {
+            return -1;
+        }
+        let connected: libc::c_int = libc::connect(
+            fd,
+            &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        );
+        if connected == -1 {
+            libc::close(fd);
+            return -1;
+        }
+        fd
+    }
+
+    unsafe fn write_all(fd: libc::c_int, mut buf: &[u8]) -> bool {
+        while !buf.is_empty() {
+            let written: isize = libc::write(fd, buf.as_ptr() as *const libc::c_void, buf.len());
+            if written <= 0 {
+                return false;
+            }
+            buf = &buf[written as usize..];
+        }
+        true
+    }
+
+    // Relays connections from the host's localhost port to the same port inside the
+    // namespace. It only starts listening once the server does, so readiness checks
+    // on the host keep working
+    unsafe fn forward_port_loop(outer_fd: libc::c_int, port: u16) {
+        loop {
+            let probe_fd: libc::c_int = connect_inner(port);
+            if probe_fd != -1 {
+                libc::close(probe_fd);
+                break;
+            }
+            libc::usleep(100_000);
+        }
+
+        let addr: libc::sockaddr_in = localhost_addr(port);
+        let reuse: libc::c_int = 1;
+        libc::setsockopt(
+            outer_fd,
+            libc::SOL_SOCKET,
+            libc::SO_REUSEADDR,
+            &reuse as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        );
+        let bound: libc::c_int = libc::bind(
+            outer_fd,
+            &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        );
+        if bound == -1 || libc::listen(outer_fd, MAX_FORWARDED_CONNECTIONS as libc::c_int) == -1 {
+            return;
+        }
+
+        // Client and server side of every relayed connection, -1 when the slot is free
+        let mut pairs: [(libc::c_int, libc::c_int); MAX_FORWARDED_CONNECTIONS] =
+            [(-1, -1); MAX_FORWARDED_CONNECTIONS];
+        let mut buf: [u8; 16_384] = [0; 16_384];
+
+        loop {
+            let mut fds: [libc::pollfd; 1 + 2 * MAX_FORWARDED_CONNECTIONS] = [libc::pollfd {
+                fd: -1,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+                1 + 2 * MAX_FORWARDED_CONNECTIONS];
+            fds[0].fd = outer_fd;
+            for (i, (client_fd, server_fd)) in pairs.iter().enumerate() {
+                fds[1 + 2 * i].fd = *client_fd;
+                fds[2 + 2 * i].fd = *server_fd;
+            }
+
+            if libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) == -1 {
+                continue;
+            }
+
+            if fds[0].revents & libc::POLLIN != 0 {
+                let client_fd: libc::c_int = libc::accept4(
+                    outer_fd,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    libc::SOCK_CLOEXEC,
+                );
+                if client_fd != -1 {
+                    let server_fd: libc::c_int = connect_inner(port);
+                    match pairs.iter_mut().find(|(fd, _)| *fd == -1) {
+                        Some(slot) if server_fd != -1 => *slot = (client_fd, server_fd),
+                        _ => {
+                            libc::close(client_fd);
+                            if server_fd != -1 {
+                                libc::close(server_fd);
+                            }
+                        }
+                    }
+                }
+            }
+
+            for (i, pair) in pairs.iter_mut().enumerate() {
+                let (client_fd, server_fd) = *pair;
+                if client_fd == -1 {
+                    continue;
+                }
+
+                let directions: [(usize, libc::c_int, libc::c_int); 2] = [
+                    (1 + 2 * i, client_fd, server_fd),
+                    (2 + 2 * i, server_fd, client_fd),
+                ];
+                for (poll_index, from_fd, to_fd) in directions {
+                    if fds[poll_index].revents == 0 || pair.0 == -1 {
+                        continue;
+                    }
+                    let read: isize =
+                        libc::read(from_fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+                    if read <= 0 || !write_all(to_fd, &buf[..read as usize]) {
+                        libc::close(client_fd);
+                        libc::close(server_fd);
+                        *pair = (-1, -1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::workspace::TempWorkspace;
+
+    fn test_sandbox(cpu_secs: u64) -> Sandbox {
+        Sandbox {
+            enabled: true,
+            isolate_network: true,
+            cpu_secs: Some(cpu_secs),
+            memory_mb: Some(DEFAULT_MEMORY_MB),
+            isolation_error: None,
+        }
+        .probe()
+    }
+
+    #[tokio::test]
+    async fn tests_reports_cpu_and_time_violations() {
+        let sandbox: Sandbox = test_sandbox(1);
+        let temp_dir: TempWorkspace = TempWorkspace::new("sandbox_limits");
+        let dir: PathBuf = temp_dir.root.clone();
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut busy: Command = sandbox.command("sh", &dir, None).unwrap();
+        busy.args(["-c", "while :; do :; done"]);
+        let output: SandboxOutput = sandbox
+            .output(busy, Duration::from_secs(30))
+            .await
+            .expect("Failed to run command");
+        assert!(!output.success);
+        assert_eq!(output.violation, Some(Violation::CpuTime(1)));
+
+        // The background sleep shares the process group and is killed with it
+        let mut sleeping: Command = sandbox.command("sh", &dir, None).unwrap();
+        sleeping.args(["-c", "sleep 30 & sleep 30"]);
+        let output: SandboxOutput = sandbox
+            .output(sleeping, Duration::from_millis(500))
+            .await
+            .expect("Failed to run command");
+        assert_eq!(
+            output.violation,
+            Some(Violation::WallTime(Duration::from_millis(500)))
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_isolates_network_to_localhost() {
+        let sandbox: Sandbox = test_sandbox(DEFAULT_CPU_SECS);
+        if let Some(isolation_error) = &sandbox.isolation_error {
+            eprintln!("Skipping network isolation test: {}", isolation_error);
+            return;
+        }
+        let temp_dir: TempWorkspace = TempWorkspace::new("sandbox_network");
+        let dir: PathBuf = temp_dir.root.clone();
+        fs::create_dir_all(&dir).unwrap();
+
+        // /proc/net/dev lists the interfaces of the reading process's namespace
+        let mut interfaces: Command = sandbox.command("cat", &dir, None).unwrap();
+        interfaces.arg("/proc/net/dev");
+        let output: SandboxOutput = sandbox
+            .output(interfaces, Duration::from_secs(10))
+            .await
+            .expect("Failed to run command");
+
+        let interface_names: Vec<&str> = output
+            .stdout
+            .lines()
+            .skip(2)
+            .filter_map(|line| line.split(':').next())
+            .map(|name| name.trim())
+            .collect();
+        assert_eq!(interface_names, vec!["lo"]);
+    }
+}